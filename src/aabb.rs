@@ -0,0 +1,162 @@
+use crate::{Interval, Point3, Ray};
+
+// Axis-aligned bounding box stored as one interval per axis
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Aabb {
+    pub x: Interval,
+    pub y: Interval,
+    pub z: Interval,
+}
+
+impl Default for Aabb {
+    fn default() -> Self {
+        Self::empty()
+    }
+}
+
+impl Aabb {
+    pub fn new(x: Interval, y: Interval, z: Interval) -> Self {
+        let mut bbox = Self { x, y, z };
+        bbox.pad_to_minimums();
+        bbox
+    }
+
+    pub fn empty() -> Self {
+        Self {
+            x: Interval::empty(),
+            y: Interval::empty(),
+            z: Interval::empty(),
+        }
+    }
+
+    // Treat the two points a and b as extrema for the bounding box, in any order
+    pub fn from_points(a: Point3, b: Point3) -> Self {
+        Self::new(
+            Interval::new(f64::min(a.x(), b.x()), f64::max(a.x(), b.x())),
+            Interval::new(f64::min(a.y(), b.y()), f64::max(a.y(), b.y())),
+            Interval::new(f64::min(a.z(), b.z()), f64::max(a.z(), b.z())),
+        )
+    }
+
+    pub fn surrounding(box0: &Aabb, box1: &Aabb) -> Self {
+        Self {
+            x: Interval::enclosing(&box0.x, &box1.x),
+            y: Interval::enclosing(&box0.y, &box1.y),
+            z: Interval::enclosing(&box0.z, &box1.z),
+        }
+    }
+
+    pub fn axis_interval(&self, n: usize) -> &Interval {
+        match n {
+            1 => &self.y,
+            2 => &self.z,
+            _ => &self.x,
+        }
+    }
+
+    // Index of the axis along which the box is widest
+    pub fn longest_axis(&self) -> usize {
+        if self.x.size() > self.y.size() {
+            if self.x.size() > self.z.size() {
+                0
+            } else {
+                2
+            }
+        } else if self.y.size() > self.z.size() {
+            1
+        } else {
+            2
+        }
+    }
+
+    pub fn centroid(&self) -> Point3 {
+        Point3::new(
+            0.5 * (self.x.min + self.x.max),
+            0.5 * (self.y.min + self.y.max),
+            0.5 * (self.z.min + self.z.max),
+        )
+    }
+
+    pub fn surface_area(&self) -> f64 {
+        let dx = self.x.size().max(0.0);
+        let dy = self.y.size().max(0.0);
+        let dz = self.z.size().max(0.0);
+        2.0 * (dx * dy + dy * dz + dz * dx)
+    }
+
+    pub fn hit(&self, r: &Ray, ray_t: Interval) -> bool {
+        self.hit_range(r, ray_t).is_some()
+    }
+
+    // The part of ray_t during which the ray is inside the box, if any
+    pub fn hit_range(&self, r: &Ray, mut ray_t: Interval) -> Option<Interval> {
+        let ray_orig = r.origin();
+        let ray_dir = r.direction();
+
+        for axis in 0..3 {
+            let ax = self.axis_interval(axis);
+            let adinv = 1.0 / ray_dir[axis];
+
+            let t0 = (ax.min - ray_orig[axis]) * adinv;
+            let t1 = (ax.max - ray_orig[axis]) * adinv;
+
+            if t0 < t1 {
+                ray_t.min = f64::max(ray_t.min, t0);
+                ray_t.max = f64::min(ray_t.max, t1);
+            } else {
+                ray_t.min = f64::max(ray_t.min, t1);
+                ray_t.max = f64::min(ray_t.max, t0);
+            }
+
+            if ray_t.max <= ray_t.min {
+                return None;
+            }
+        }
+        Some(ray_t)
+    }
+
+    fn pad_to_minimums(&mut self) {
+        // Adjust the box so that no side is narrower than some delta, padding if necessary
+        let delta = 0.0001;
+        if self.x.size() < delta {
+            self.x = self.x.expand(delta);
+        }
+        if self.y.size() < delta {
+            self.y = self.y.expand(delta);
+        }
+        if self.z.size() < delta {
+            self.z = self.z.expand(delta);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Vec3;
+
+    #[test]
+    fn test_hit_through_box() {
+        let bbox = Aabb::from_points(Point3::new(-1.0, -1.0, -1.0), Point3::new(1.0, 1.0, 1.0));
+        let r = Ray::new(Point3::new(0.0, 0.0, -5.0), Vec3::new(0.0, 0.0, 1.0));
+        assert!(bbox.hit(&r, Interval::new(0.001, f64::INFINITY)));
+    }
+
+    #[test]
+    fn test_miss_beside_box() {
+        let bbox = Aabb::from_points(Point3::new(-1.0, -1.0, -1.0), Point3::new(1.0, 1.0, 1.0));
+        let r = Ray::new(Point3::new(2.0, 0.0, -5.0), Vec3::new(0.0, 0.0, 1.0));
+        assert!(!bbox.hit(&r, Interval::new(0.001, f64::INFINITY)));
+    }
+
+    #[test]
+    fn test_surrounding_and_longest_axis() {
+        let a = Aabb::from_points(Point3::new(0.0, 0.0, 0.0), Point3::new(1.0, 1.0, 1.0));
+        let b = Aabb::from_points(Point3::new(4.0, 0.0, 0.0), Point3::new(5.0, 2.0, 1.0));
+        let both = Aabb::surrounding(&a, &b);
+        assert_eq!(both.x, Interval::new(0.0, 5.0));
+        assert_eq!(both.y, Interval::new(0.0, 2.0));
+        assert_eq!(both.longest_axis(), 0);
+        assert_eq!(both.surface_area(), 2.0 * (10.0 + 2.0 + 5.0));
+    }
+}
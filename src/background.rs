@@ -0,0 +1,33 @@
+use crate::{unit_vector, Color, Ray};
+
+// Radiance returned for rays that escape the scene
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Background {
+    None,                                   // Black, for closed scenes lit by emitters
+    Solid(Color),                           // The same color in every direction
+    Gradient { bottom: Color, top: Color }, // Vertical blend on the ray direction
+}
+
+impl Default for Background {
+    fn default() -> Self {
+        // The sky gradient used before backgrounds were configurable
+        Background::Gradient {
+            bottom: Color::new(1.0, 1.0, 1.0),
+            top: Color::new(0.5, 0.7, 1.0),
+        }
+    }
+}
+
+impl Background {
+    pub fn color(&self, r: &Ray) -> Color {
+        match self {
+            Background::None => Color::new(0.0, 0.0, 0.0),
+            Background::Solid(color) => *color,
+            Background::Gradient { bottom, top } => {
+                let unit_direction = unit_vector(r.direction());
+                let a = 0.5 * (unit_direction.y() + 1.0);
+                (1.0 - a) * *bottom + a * *top
+            }
+        }
+    }
+}
@@ -0,0 +1,389 @@
+use crate::{Aabb, HitRecord, Hittable, HittableList, Interval, Point3, Ray};
+use std::sync::Arc;
+
+// Largest number of objects the SAH builder is allowed to keep in a single leaf
+const MAX_LEAF_SIZE: usize = 4;
+// Number of buckets the SAH builder bins object centroids into
+const SAH_BUCKETS: usize = 12;
+// Cost of visiting an interior node, relative to intersecting one object
+const SAH_TRAVERSAL_COST: f64 = 1.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SplitStrategy {
+    Midpoint, // Split at the middle of the widest axis of the object centroids
+    Sah,      // Pick the split minimising the surface area heuristic
+}
+
+// Summary of a built tree, used to judge its quality
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct BvhStats {
+    pub node_count: usize,    // Interior and leaf nodes together
+    pub leaf_count: usize,    // Nodes holding objects
+    pub object_count: usize,  // Objects stored across all leaves
+    pub max_depth: usize,     // Depth of the deepest leaf (the root is depth 0)
+    pub min_leaf_size: usize, // Fewest objects in any leaf
+    pub max_leaf_size: usize, // Most objects in any leaf
+}
+
+impl BvhStats {
+    pub fn average_leaf_size(&self) -> f64 {
+        if self.leaf_count == 0 {
+            return 0.0;
+        }
+        self.object_count as f64 / self.leaf_count as f64
+    }
+}
+
+enum BvhContents {
+    Leaf(Vec<Arc<dyn Hittable>>),
+    Interior(Box<BvhNode>, Box<BvhNode>),
+}
+
+pub struct BvhNode {
+    bbox: Aabb,
+    contents: BvhContents,
+    has_media: bool, // Some object below is a participating medium
+}
+
+impl BvhNode {
+    pub fn new(list: HittableList, strategy: SplitStrategy) -> Self {
+        Self::build(list.objects().to_vec(), strategy)
+    }
+
+    pub fn stats(&self) -> BvhStats {
+        let mut stats = BvhStats {
+            min_leaf_size: usize::MAX,
+            ..BvhStats::default()
+        };
+        self.collect_stats(0, &mut stats);
+        if stats.leaf_count == 0 {
+            stats.min_leaf_size = 0;
+        }
+        stats
+    }
+
+    fn build(mut objects: Vec<Arc<dyn Hittable>>, strategy: SplitStrategy) -> Self {
+        let bbox = objects.iter().fold(Aabb::empty(), |bbox, object| {
+            Aabb::surrounding(&bbox, &object.bounding_box())
+        });
+
+        if objects.len() <= 1 {
+            return Self::leaf(bbox, objects);
+        }
+
+        // Split along the axis where the centroids are most spread out
+        let centroid_bounds = objects.iter().fold(Aabb::empty(), |bounds, object| {
+            let c = object.bounding_box().centroid();
+            Aabb::surrounding(&bounds, &Aabb::from_points(c, c))
+        });
+        let axis = centroid_bounds.longest_axis();
+
+        let split = match strategy {
+            SplitStrategy::Midpoint => {
+                if objects.len() <= 2 {
+                    return Self::leaf(bbox, objects);
+                }
+                Self::split_midpoint(&mut objects, axis, &centroid_bounds)
+            }
+            SplitStrategy::Sah => {
+                match Self::split_sah(&mut objects, axis, &bbox, &centroid_bounds) {
+                    Some(split) => split,
+                    None => return Self::leaf(bbox, objects),
+                }
+            }
+        };
+
+        let right_objects = objects.split_off(split);
+        let left = Self::build(objects, strategy);
+        let right = Self::build(right_objects, strategy);
+
+        Self {
+            bbox,
+            has_media: left.has_media || right.has_media,
+            contents: BvhContents::Interior(Box::new(left), Box::new(right)),
+        }
+    }
+
+    fn leaf(bbox: Aabb, objects: Vec<Arc<dyn Hittable>>) -> Self {
+        Self {
+            bbox,
+            has_media: objects.iter().any(|object| object.has_media()),
+            contents: BvhContents::Leaf(objects),
+        }
+    }
+
+    // Partition the objects around the centroid midpoint and return the split index
+    fn split_midpoint(
+        objects: &mut [Arc<dyn Hittable>],
+        axis: usize,
+        centroid_bounds: &Aabb,
+    ) -> usize {
+        let extent = centroid_bounds.axis_interval(axis);
+        let mid = 0.5 * (extent.min + extent.max);
+        let split = partition(objects, |object| centroid(object)[axis] < mid);
+
+        if split == 0 || split == objects.len() {
+            return split_median(objects, axis);
+        }
+        split
+    }
+
+    // Bin centroids into buckets and partition at the cheapest bucket boundary.
+    // Returns None when keeping the objects in one leaf is cheaper than any split.
+    fn split_sah(
+        objects: &mut [Arc<dyn Hittable>],
+        axis: usize,
+        bbox: &Aabb,
+        centroid_bounds: &Aabb,
+    ) -> Option<usize> {
+        let extent = *centroid_bounds.axis_interval(axis);
+        if extent.size() <= 0.0 {
+            // Every centroid coincides, so no bucket boundary can separate them
+            if objects.len() <= MAX_LEAF_SIZE {
+                return None;
+            }
+            return Some(split_median(objects, axis));
+        }
+
+        let bucket_of = |object: &Arc<dyn Hittable>| -> usize {
+            let offset = (centroid(object)[axis] - extent.min) / extent.size();
+            usize::min((offset * SAH_BUCKETS as f64) as usize, SAH_BUCKETS - 1)
+        };
+
+        let mut counts = [0usize; SAH_BUCKETS];
+        let mut bounds = [Aabb::empty(); SAH_BUCKETS];
+        for object in objects.iter() {
+            let b = bucket_of(object);
+            counts[b] += 1;
+            bounds[b] = Aabb::surrounding(&bounds[b], &object.bounding_box());
+        }
+
+        let mut best_cost = f64::INFINITY;
+        let mut best_bucket = 0;
+        for split in 1..SAH_BUCKETS {
+            let (left_count, left_box) = accumulate(&counts[..split], &bounds[..split]);
+            let (right_count, right_box) = accumulate(&counts[split..], &bounds[split..]);
+            if left_count == 0 || right_count == 0 {
+                continue;
+            }
+
+            let cost = SAH_TRAVERSAL_COST
+                + (left_count as f64 * left_box.surface_area()
+                    + right_count as f64 * right_box.surface_area())
+                    / bbox.surface_area();
+            if cost < best_cost {
+                best_cost = cost;
+                best_bucket = split;
+            }
+        }
+
+        let leaf_cost = objects.len() as f64;
+        if objects.len() <= MAX_LEAF_SIZE && leaf_cost <= best_cost {
+            return None;
+        }
+        if best_cost == f64::INFINITY {
+            return Some(split_median(objects, axis));
+        }
+        Some(partition(objects, |object| bucket_of(object) < best_bucket))
+    }
+
+    fn collect_stats(&self, depth: usize, stats: &mut BvhStats) {
+        stats.node_count += 1;
+        match &self.contents {
+            BvhContents::Leaf(objects) => {
+                stats.leaf_count += 1;
+                stats.object_count += objects.len();
+                stats.max_depth = usize::max(stats.max_depth, depth);
+                stats.min_leaf_size = usize::min(stats.min_leaf_size, objects.len());
+                stats.max_leaf_size = usize::max(stats.max_leaf_size, objects.len());
+            }
+            BvhContents::Interior(left, right) => {
+                left.collect_stats(depth + 1, stats);
+                right.collect_stats(depth + 1, stats);
+            }
+        }
+    }
+}
+
+impl Hittable for BvhNode {
+    fn hit_tmin_tmax(&self, r: &Ray, ray_tmin: f64, ray_tmax: f64, rec: &mut HitRecord) -> bool {
+        self.hit_interval(r, Interval::new(ray_tmin, ray_tmax), rec)
+    }
+
+    fn hit_interval(&self, r: &Ray, ray_t: Interval, rec: &mut HitRecord) -> bool {
+        if !self.bbox.hit(r, ray_t) {
+            return false;
+        }
+
+        match &self.contents {
+            BvhContents::Leaf(objects) => {
+                let mut hit_anything = false;
+                let mut closest_so_far = ray_t.max;
+                for object in objects {
+                    if object.hit_interval(r, Interval::new(ray_t.min, closest_so_far), rec) {
+                        hit_anything = true;
+                        closest_so_far = rec.t;
+                    }
+                }
+                hit_anything
+            }
+            BvhContents::Interior(left, right) => {
+                let hit_left = left.hit_interval(r, ray_t, rec);
+                let right_max = if hit_left { rec.t } else { ray_t.max };
+                let hit_right = right.hit_interval(r, Interval::new(ray_t.min, right_max), rec);
+                hit_left || hit_right
+            }
+        }
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.bbox
+    }
+
+    fn has_media(&self) -> bool {
+        self.has_media
+    }
+
+    fn hit_surface(&self, r: &Ray, ray_t: Interval, rec: &mut HitRecord) -> bool {
+        if !self.has_media {
+            return self.hit_interval(r, ray_t, rec);
+        }
+        if !self.bbox.hit(r, ray_t) {
+            return false;
+        }
+
+        match &self.contents {
+            BvhContents::Leaf(objects) => {
+                let mut hit_anything = false;
+                let mut closest_so_far = ray_t.max;
+                for object in objects {
+                    if object.hit_surface(r, Interval::new(ray_t.min, closest_so_far), rec) {
+                        hit_anything = true;
+                        closest_so_far = rec.t;
+                    }
+                }
+                hit_anything
+            }
+            BvhContents::Interior(left, right) => {
+                let hit_left = left.hit_surface(r, ray_t, rec);
+                let right_max = if hit_left { rec.t } else { ray_t.max };
+                let hit_right = right.hit_surface(r, Interval::new(ray_t.min, right_max), rec);
+                hit_left || hit_right
+            }
+        }
+    }
+
+    fn transmittance(&self, r: &Ray, ray_t: Interval) -> f64 {
+        if !self.has_media || !self.bbox.hit(r, ray_t) {
+            return 1.0;
+        }
+
+        match &self.contents {
+            BvhContents::Leaf(objects) => objects
+                .iter()
+                .map(|object| object.transmittance(r, ray_t))
+                .product(),
+            BvhContents::Interior(left, right) => {
+                left.transmittance(r, ray_t) * right.transmittance(r, ray_t)
+            }
+        }
+    }
+}
+
+fn centroid(object: &Arc<dyn Hittable>) -> Point3 {
+    object.bounding_box().centroid()
+}
+
+fn accumulate(counts: &[usize], bounds: &[Aabb]) -> (usize, Aabb) {
+    let count = counts.iter().sum();
+    let bbox = bounds
+        .iter()
+        .fold(Aabb::empty(), |acc, b| Aabb::surrounding(&acc, b));
+    (count, bbox)
+}
+
+// Sort by centroid along the axis and split the objects into two equal halves
+fn split_median(objects: &mut [Arc<dyn Hittable>], axis: usize) -> usize {
+    objects.sort_by(|a, b| centroid(a)[axis].total_cmp(&centroid(b)[axis]));
+    objects.len() / 2
+}
+
+// Move the objects matching the predicate to the front and return how many there are
+fn partition<F>(objects: &mut [Arc<dyn Hittable>], pred: F) -> usize
+where
+    F: Fn(&Arc<dyn Hittable>) -> bool,
+{
+    let mut split = 0;
+    for i in 0..objects.len() {
+        if pred(&objects[i]) {
+            objects.swap(i, split);
+            split += 1;
+        }
+    }
+    split
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{unit_vector, DefaultMaterial, IndependentSampler, Sampler, Sphere, Vec3};
+
+    fn random_spheres(count: usize) -> HittableList {
+        let mut sampler = IndependentSampler::new(7);
+        let mat = Arc::new(DefaultMaterial::new());
+        let mut list = HittableList::new();
+        for _ in 0..count {
+            let center = Vec3::random_range(-10.0, 10.0, &mut sampler);
+            list.add(Arc::new(Sphere::new(
+                center,
+                0.1 + 0.4 * sampler.random_double(),
+                mat.clone(),
+            )));
+        }
+        list
+    }
+
+    fn assert_matches_list(strategy: SplitStrategy) {
+        let list = random_spheres(200);
+        let bvh = BvhNode::new(random_spheres(200), strategy);
+        let mut sampler = IndependentSampler::new(11);
+
+        for _ in 0..500 {
+            let r = Ray::new(
+                Vec3::random_range(-15.0, 15.0, &mut sampler),
+                unit_vector(Vec3::random_range(-1.0, 1.0, &mut sampler)),
+            );
+            let mut list_rec = HitRecord::new();
+            let mut bvh_rec = HitRecord::new();
+            let ray_t = Interval::new(0.001, f64::INFINITY);
+            let list_hit = list.hit_interval(&r, ray_t, &mut list_rec);
+            let bvh_hit = bvh.hit_interval(&r, ray_t, &mut bvh_rec);
+            assert_eq!(list_hit, bvh_hit);
+            if list_hit {
+                assert_eq!(list_rec.t, bvh_rec.t);
+            }
+        }
+    }
+
+    #[test]
+    fn test_midpoint_matches_list() {
+        assert_matches_list(SplitStrategy::Midpoint);
+    }
+
+    #[test]
+    fn test_sah_matches_list() {
+        assert_matches_list(SplitStrategy::Sah);
+    }
+
+    #[test]
+    fn test_stats_account_for_every_object() {
+        for strategy in [SplitStrategy::Midpoint, SplitStrategy::Sah] {
+            let stats = BvhNode::new(random_spheres(100), strategy).stats();
+            assert_eq!(stats.object_count, 100);
+            assert_eq!(stats.node_count, 2 * stats.leaf_count - 1);
+            assert!(stats.min_leaf_size >= 1);
+            assert!(stats.max_leaf_size <= MAX_LEAF_SIZE);
+            assert!(stats.max_depth > 0);
+        }
+    }
+}
@@ -1,32 +1,76 @@
-use std::fs::File;
-use std::io::Write;
-
-use image::ImageFormat;
 use indicatif::{ProgressBar, ProgressStyle};
+use rayon::prelude::*;
 
 use crate::{
-    degrees_to_radians, random_double, unit_vector, vec3::cross, write_color, Color, HitRecord,
-    Hittable, Interval, Point3, Ray, Vec3, INFINITY,
+    degrees_to_radians, unit_vector,
+    vec3::{cross, random_in_unit_disk},
+    Background, Color, Framebuffer, HitRecord, Hittable, Interval, Point3, Ray, Sampler,
+    SamplerType, Vec3, INFINITY,
 };
 
+// Settings of adaptive sampling. Every pixel takes at least min_samples; it then keeps sampling
+// until the standard error of its mean luminance, relative to that mean, falls below threshold,
+// or max_samples is reached.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AdaptiveSampling {
+    pub threshold: f64,
+    pub min_samples: u32,
+    pub max_samples: u32,
+}
+
+impl Default for AdaptiveSampling {
+    fn default() -> Self {
+        Self {
+            threshold: 0.01,
+            min_samples: 16,
+            max_samples: 1024,
+        }
+    }
+}
+
+// Luminance below which the relative error is measured against this floor instead, so black
+// pixels do not need endless samples
+const MIN_ADAPTIVE_LUMINANCE: f64 = 0.01;
+
 pub struct Camera {
     pub aspect_ratio: f64,      // Ratio of image width over height
     pub image_width: u32,       // Rendered image width in pixel count
     pub samples_per_pixel: u32, // Count of random samples for each pixel
     pub max_depth: u32,         // Maximum number of ray bounces into scene
+    pub roulette_depth: u32,    // Bounces before Russian roulette may end a path
     pub fvov: f64,              // Vertical view angle (field of view)
     pub lookfrom: Point3,       // Point camera is looking from
     pub lookat: Point3,         // Point camera is looking at
     pub vup: Vec3,              // Camera-relative "up" direction
-    image_height: u32,          // Rendered image height
-    center: Point3,             // Camera center
-    pixel00_loc: Point3,        // Location of pixel 0, 0
-    pixel_delta_u: Vec3,        // Offset to pixel to the right
-    pixel_delta_v: Vec3,        // Offset to pixel below
-    pixel_samples_scale: f64,   // Color scale factor for a sum of pixel samples
-    u: Vec3,                    // Camera frame basis vector
-    v: Vec3,                    // Camera frame basis vector
-    w: Vec3,                    // Camera frame basis vector
+    pub defocus_angle: f64,     // Variation angle of rays through each pixel
+    pub focus_dist: f64,        // Distance from camera lookfrom point to plane of perfect focus
+    pub shutter_open: f64,      // Time at which the shutter opens
+    pub shutter_close: f64,     // Time at which the shutter closes
+    pub background: Background, // Scene background color
+    pub thread_count: usize,    // Worker threads used by render (0 = one per core)
+    pub seed: Option<u64>,      // Fixed seed for reproducible renders (None = random)
+    pub sampler: SamplerType,   // Sample generator for pixel, lens, time and bounce dimensions
+    pub show_progress: bool,    // Draw a progress bar on stderr while rendering
+
+    pub adaptive: Option<AdaptiveSampling>, // Adaptive sampling bounds (None = samples_per_pixel)
+
+    image_height: u32,       // Rendered image height
+    center: Point3,          // Camera center
+    pixel00_loc: Point3,     // Location of pixel 0, 0
+    pixel_delta_u: Vec3,     // Offset to pixel to the right
+    pixel_delta_v: Vec3,     // Offset to pixel below
+    sample_counts: Vec<u32>, // Samples taken by each pixel of the last render
+    u: Vec3,                 // Camera frame basis vector
+    v: Vec3,                 // Camera frame basis vector
+    w: Vec3,                 // Camera frame basis vector
+    defocus_disk_u: Vec3,    // Defocus disk horizontal radius
+    defocus_disk_v: Vec3,    // Defocus disk vertical radius
+}
+
+impl Default for Camera {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl Camera {
@@ -36,31 +80,48 @@ impl Camera {
             image_width: 100,
             image_height: 100,
             max_depth: 10,
+            roulette_depth: 3,
             center: Point3::new(0.0, 0.0, 0.0),
             pixel00_loc: Point3::new(0.0, 0.0, 0.0),
             pixel_delta_u: Vec3::new(0.0, 0.0, 0.0),
             pixel_delta_v: Vec3::new(0.0, 0.0, 0.0),
             samples_per_pixel: 1,
-            pixel_samples_scale: 1.0,
+            sample_counts: Vec::new(),
             fvov: 90.0,
             lookfrom: Point3::new(0.0, 0.0, 0.0),
             lookat: Point3::new(0.0, 0.0, -1.0),
             vup: Vec3::new(0.0, 1.0, 0.0),
+            defocus_angle: 0.0,
+            focus_dist: 10.0,
+            shutter_open: 0.0,
+            shutter_close: 1.0,
+            background: Background::default(),
+            thread_count: 0,
+            seed: None,
+            sampler: SamplerType::default(),
+            show_progress: true,
+            adaptive: None,
             u: Vec3::new(0.0, 0.0, 0.0),
             v: Vec3::new(0.0, 0.0, 0.0),
             w: Vec3::new(0.0, 0.0, 0.0),
+            defocus_disk_u: Vec3::new(0.0, 0.0, 0.0),
+            defocus_disk_v: Vec3::new(0.0, 0.0, 0.0),
         }
     }
 
-    pub fn render(&mut self, world: &dyn Hittable) {
+    // Render the world into a linear-light framebuffer. lights holds the emitters that are
+    // sampled directly at diffuse hits; it may be an empty list.
+    pub fn render(&mut self, world: &dyn Hittable, lights: &dyn Hittable) -> Framebuffer {
         self.initialize();
 
-        let mut file = File::create("image.ppm").expect("Unable to create file");
-
         // Indicatif setup
         let total = self.image_height as u64;
         // Create a progress bar
-        let pb = ProgressBar::new(total);
+        let pb = if self.show_progress {
+            ProgressBar::new(total)
+        } else {
+            ProgressBar::hidden()
+        };
 
         // Customize the style
         pb.set_style(
@@ -72,26 +133,134 @@ impl Camera {
             .progress_chars("#>-"),
         );
 
-        writeln!(file, "P3\n{} {}\n255", self.image_width, self.image_height)
-            .expect("Unable to write header to file");
+        let pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(self.thread_count)
+            .build()
+            .expect("Unable to start render threads");
+        let seed = self.seed.unwrap_or_else(rand::random);
+
+        // Scanlines are rendered in parallel and collected back in image order
+        let camera: &Camera = self;
+        let scanlines: Vec<Vec<(Color, u32)>> = pool.install(|| {
+            (0..camera.image_height)
+                .into_par_iter()
+                .map(|j| {
+                    let scanline = camera.render_scanline(world, lights, j, seed);
+                    pb.inc(1);
+                    scanline
+                })
+                .collect()
+        });
+
+        pb.finish_with_message("Done");
+
+        let (pixels, sample_counts) = scanlines.into_iter().flatten().unzip();
+        self.sample_counts = sample_counts;
+        Framebuffer::from_pixels(self.image_width, self.image_height, pixels)
+    }
+
+    // Number of samples each pixel of the last render took, row by row from the top
+    pub fn sample_counts(&self) -> &[u32] {
+        &self.sample_counts
+    }
+
+    // Sample counts of the last render as a greyscale image, white being the most samples
+    // any pixel took. Used to tune adaptive sampling.
+    pub fn sample_count_image(&self) -> Framebuffer {
+        let most = self.sample_counts.iter().copied().max().unwrap_or(1).max(1) as f64;
+        let pixels = self
+            .sample_counts
+            .iter()
+            .map(|&count| {
+                let level = count as f64 / most;
+                Color::new(level, level, level)
+            })
+            .collect();
+        Framebuffer::from_pixels(self.image_width, self.image_height, pixels)
+    }
+
+    // Render a single pixel. With a fixed seed the result is bit-identical to the same pixel
+    // of a full render, which makes it possible to debug one pixel in isolation.
+    pub fn render_pixel(
+        &mut self,
+        world: &dyn Hittable,
+        lights: &dyn Hittable,
+        i: u32,
+        j: u32,
+    ) -> Color {
+        self.initialize();
+        let seed = self.seed.unwrap_or_else(rand::random);
+        let mut sampler = self.sampler.create(seed, self.strata_samples());
+        self.pixel_color(world, lights, i, j, sampler.as_mut()).0
+    }
 
-        for j in 0..self.image_height {
-            pb.inc(1);
-            for i in 0..self.image_width {
-                let mut pixel_color = Color::new(0.0, 0.0, 0.0);
-                for _sample in 0..self.samples_per_pixel {
-                    let r = Self::get_ray(self, i, j);
-                    pixel_color += Self::ray_color(self, &r, self.max_depth, world);
+    fn render_scanline(
+        &self,
+        world: &dyn Hittable,
+        lights: &dyn Hittable,
+        j: u32,
+        seed: u64,
+    ) -> Vec<(Color, u32)> {
+        let mut sampler = self.sampler.create(seed, self.strata_samples());
+        (0..self.image_width)
+            .map(|i| self.pixel_color(world, lights, i, j, sampler.as_mut()))
+            .collect()
+    }
+
+    // Sample count the stratified sampler divides each dimension into. Adaptive pixels all take
+    // min_samples, so those form one full set of strata; later samples wrap around onto fresh
+    // jitter in the same strata, one set at a time.
+    fn strata_samples(&self) -> u32 {
+        match self.adaptive {
+            Some(adaptive) => adaptive.min_samples,
+            None => self.samples_per_pixel,
+        }
+    }
+
+    // Average of the pixel samples, and how many samples were taken
+    fn pixel_color(
+        &self,
+        world: &dyn Hittable,
+        lights: &dyn Hittable,
+        i: u32,
+        j: u32,
+        sampler: &mut dyn Sampler,
+    ) -> (Color, u32) {
+        let (min_samples, max_samples) = match self.adaptive {
+            Some(adaptive) => (adaptive.min_samples, adaptive.max_samples),
+            None => (self.samples_per_pixel, self.samples_per_pixel),
+        };
+
+        let mut pixel_color = Color::new(0.0, 0.0, 0.0);
+        // Running mean and sum of squared deviations of the sample luminance (Welford)
+        let mut mean = 0.0;
+        let mut m2 = 0.0;
+        let mut count = 0;
+        while count < max_samples {
+            // Every sample draws from its own stream, keyed on the pixel and sample index,
+            // so the image does not depend on thread count or scheduling
+            sampler.start_pixel_sample(i, j, count);
+            let r = self.get_ray(i, j, sampler);
+            let sample = self.ray_color(&r, world, lights, sampler);
+            pixel_color += sample;
+            count += 1;
+
+            let luminance = 0.2126 * sample.x() + 0.7152 * sample.y() + 0.0722 * sample.z();
+            let delta = luminance - mean;
+            mean += delta / count as f64;
+            m2 += delta * (luminance - mean);
+
+            if let Some(adaptive) = self.adaptive {
+                if count >= min_samples.max(2) {
+                    let n = count as f64;
+                    let standard_error = (m2 / (n - 1.0) / n).sqrt();
+                    if standard_error <= adaptive.threshold * mean.max(MIN_ADAPTIVE_LUMINANCE) {
+                        break;
+                    }
                 }
-                write_color(&file, &(self.pixel_samples_scale * pixel_color));
             }
         }
-        let img = image::open("image.ppm").expect("Unable to write image to file");
-
-        // Save it as a JPEG
-        img.save_with_format("output.jpg", ImageFormat::Jpeg)
-            .expect("Unable to write image to file");
-        pb.finish_with_message("Done");
+        (pixel_color / count.max(1) as f64, count)
     }
 
     fn initialize(&mut self) {
@@ -101,15 +270,13 @@ impl Camera {
         } else {
             self.image_height
         };
-        self.pixel_samples_scale = 1.0 / (self.samples_per_pixel as f64);
 
-        let center = self.lookfrom;
+        self.center = self.lookfrom;
 
         // Determine the viewport dimensions.
-        let focal_length: f64 = (self.lookfrom - self.lookat).length();
         let theta = degrees_to_radians(self.fvov);
         let h = f64::tan(theta / 2.0);
-        let viewport_height = 2.0 * h * focal_length;
+        let viewport_height = 2.0 * h * self.focus_dist;
         let viewport_width: f64 =
             viewport_height * (self.image_width as f64) / (self.image_height as f64);
 
@@ -128,47 +295,440 @@ impl Camera {
 
         // Calculate the location of the upper left pixel
         let viewport_upper_left =
-            center - (focal_length * self.w) - viewport_u / 2.0 - viewport_v / 2.0;
+            self.center - (self.focus_dist * self.w) - viewport_u / 2.0 - viewport_v / 2.0;
         self.pixel00_loc = viewport_upper_left + 0.5 * (self.pixel_delta_u + self.pixel_delta_v);
+
+        // Calculate the camera defocus disk basis vectors.
+        let defocus_radius =
+            self.focus_dist * f64::tan(degrees_to_radians(self.defocus_angle / 2.0));
+        self.defocus_disk_u = self.u * defocus_radius;
+        self.defocus_disk_v = self.v * defocus_radius;
     }
 
-    fn ray_color(&self, r: &Ray, depth: u32, world: &dyn Hittable) -> Color {
-        if depth <= 0 {
-            return Color::new(0.0, 0.0, 0.0);
-        }
+    // Radiance arriving along r, following the path bounce by bounce while carrying its
+    // throughput: the product of the sample weights so far
+    fn ray_color(
+        &self,
+        r: &Ray,
+        world: &dyn Hittable,
+        lights: &dyn Hittable,
+        sampler: &mut dyn Sampler,
+    ) -> Color {
+        let mut radiance = Color::new(0.0, 0.0, 0.0);
+        let mut throughput = Color::new(1.0, 1.0, 1.0);
+        let mut ray = Ray::new_with_time(r.origin(), r.direction(), r.time());
+        // Density with which the previous hit picked the ray, or None for the camera ray and
+        // specular bounces, whose emission always counts in full
+        let mut bsdf_pdf: Option<f64> = None;
 
-        let mut rec = HitRecord::new();
+        for depth in 0..self.max_depth {
+            let mut rec = HitRecord::new();
 
-        if world.hit_interval(r, Interval::new(0.001, INFINITY), &mut rec) {
-            let mut scattered = Ray::new(Point3::new(0.0, 0.0, 0.0), Vec3::new(0.0, 0.0, 0.0));
-            let mut attenuation = Color::new(0.0, 0.0, 0.0);
-            if rec.mat.scatter(r, &rec, &mut attenuation, &mut scattered) {
-                return attenuation * self.ray_color(&scattered, depth - 1, world);
+            // If the ray hits nothing, add the background color
+            if !world.hit_interval(&ray, Interval::new(0.001, INFINITY), &mut rec) {
+                radiance += throughput * self.background.color(&ray);
+                break;
+            }
+
+            // Emitters the lights could also have sampled share their light with that strategy
+            let emitted = rec.mat.emitted(rec.u, rec.v, &rec.p);
+            radiance += throughput
+                * match bsdf_pdf {
+                    Some(pdf) => emitted * power_heuristic(pdf, lights.pdf_value(&ray)),
+                    None => emitted,
+                };
+
+            // Light sampling runs before the material picks a lobe, so a specular lobe can't
+            // take the direct light from the others. eval leaves the specular lobes out.
+            radiance += throughput * self.sample_lights(&ray, &rec, world, lights, sampler);
+
+            let Some(bsdf) = rec.mat.sample(&ray, &rec, sampler) else {
+                break;
+            };
+            bsdf_pdf = if bsdf.specular { None } else { Some(bsdf.pdf) };
+            throughput = throughput * bsdf.weight;
+            ray = Ray::new_with_time(rec.p, bsdf.direction, ray.time());
+
+            // Russian roulette: end dim paths at random, boosting the survivors so the
+            // expected value stays the same
+            if depth + 1 >= self.roulette_depth {
+                let survival = throughput.x().max(throughput.y()).max(throughput.z());
+                if survival < 1.0 {
+                    if sampler.get_1d() >= survival {
+                        break;
+                    }
+                    throughput /= survival;
+                }
             }
-            return Color::new(0.0, 0.0, 0.0);
         }
 
-        let unit_direction = unit_vector(r.direction());
+        radiance
+    }
 
-        let a = 0.5 * (unit_direction.y() + 1.0);
-        return (1.0 - a) * Color::new(1.0, 1.0, 1.0) + a * Color::new(0.5, 0.7, 1.0);
+    // Next-event estimation: the light arriving straight from a point sampled on the lights,
+    // weighted against the chance that the material would have picked the same direction
+    fn sample_lights(
+        &self,
+        r_in: &Ray,
+        rec: &HitRecord,
+        world: &dyn Hittable,
+        lights: &dyn Hittable,
+        sampler: &mut dyn Sampler,
+    ) -> Color {
+        let Some(sample) = lights.sample(&rec.p, r_in.time(), sampler) else {
+            return Color::new(0.0, 0.0, 0.0);
+        };
+        let direction = sample.p - rec.p;
+        let f = rec.mat.eval(r_in, rec, &direction);
+        if sample.pdf <= 0.0 || f.near_zero() {
+            return Color::new(0.0, 0.0, 0.0);
+        }
+
+        // The shadow ray returns the first surface it hits, so occluders give no light. Media
+        // in between dim it instead.
+        let shadow_ray = Ray::new_with_time(rec.p, direction, r_in.time());
+        let mut light_rec = HitRecord::new();
+        if !world.hit_surface(&shadow_ray, Interval::new(0.001, INFINITY), &mut light_rec) {
+            return Color::new(0.0, 0.0, 0.0);
+        }
+        let emitted = light_rec
+            .mat
+            .emitted(light_rec.u, light_rec.v, &light_rec.p)
+            * world.transmittance(&shadow_ray, Interval::new(0.001, light_rec.t));
+        let weight = power_heuristic(sample.pdf, rec.mat.pdf(r_in, rec, &direction));
+        emitted * f * (weight / sample.pdf)
     }
 
-    fn get_ray(&self, i: u32, j: u32) -> Ray {
-        // Construct a camera ray originating from the origin and directed at randomly sampled
-        // point around the pixel location i,j
+    fn get_ray(&self, i: u32, j: u32, sampler: &mut dyn Sampler) -> Ray {
+        // Construct a camera ray originating from the defocus disk and directed at a randomly
+        // sampled point around the pixel location i, j.
 
-        let offset = Self::sample_square();
+        let offset = Self::sample_square(sampler);
         let pixel_sample = self.pixel00_loc
             + ((i as f64 + offset.x()) * self.pixel_delta_u)
             + ((j as f64 + offset.y()) * self.pixel_delta_v);
-        let ray_origin = self.center;
+        // The lens sample is drawn even without defocus blur so every sample uses the same
+        // sampler dimensions for the same purpose
+        let lens_sample = self.defocus_disk_sample(sampler);
+        let ray_origin = if self.defocus_angle <= 0.0 {
+            self.center
+        } else {
+            lens_sample
+        };
         let ray_direction = pixel_sample - ray_origin;
+        let ray_time =
+            self.shutter_open + (self.shutter_close - self.shutter_open) * sampler.get_1d();
+
+        Ray::new_with_time(ray_origin, ray_direction, ray_time)
+    }
+
+    fn defocus_disk_sample(&self, sampler: &mut dyn Sampler) -> Point3 {
+        // Returns a random point in the camera defocus disk.
+        let p = random_in_unit_disk(sampler);
+        self.center + (p[0] * self.defocus_disk_u) + (p[1] * self.defocus_disk_v)
+    }
+
+    fn sample_square(sampler: &mut dyn Sampler) -> Vec3 {
+        let (x, y) = sampler.get_2d();
+        Vec3::new(x - 0.5, y - 0.5, 0.0)
+    }
+}
+
+// Multiple importance sampling weight of a strategy with density pdf, against another strategy
+// with density other_pdf (Veach's power heuristic with exponent 2)
+fn power_heuristic(pdf: f64, other_pdf: f64) -> f64 {
+    let (a, b) = (pdf * pdf, other_pdf * other_pdf);
+    if a + b == 0.0 {
+        return 0.0;
+    }
+    a / (a + b)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        Dielectric, DiffuseLight, HittableList, Lambertian, Metal, Principled, Quad, Sphere,
+    };
+    use std::sync::Arc;
+
+    // The world and its light list
+    fn test_world() -> (HittableList, HittableList) {
+        let mut world = HittableList::new();
+        let light: Arc<dyn Hittable> = Arc::new(Quad::new(
+            Point3::new(-0.5, 1.5, -1.5),
+            Vec3::new(1.0, 0.0, 0.0),
+            Vec3::new(0.0, 0.0, 1.0),
+            Arc::new(DiffuseLight::new(Color::new(4.0, 4.0, 4.0))),
+        ));
+        world.add(light.clone());
+        let mut lights = HittableList::new();
+        lights.add(light);
+        world.add(Arc::new(Sphere::new(
+            Point3::new(0.0, -100.5, -1.0),
+            100.0,
+            Arc::new(Lambertian::new(Color::new(0.8, 0.8, 0.0))),
+        )));
+        world.add(Arc::new(Sphere::new(
+            Point3::new(-0.6, 0.0, -1.0),
+            0.5,
+            Arc::new(Dielectric::new(1.5)),
+        )));
+        world.add(Arc::new(Sphere::new(
+            Point3::new(0.6, 0.0, -1.0),
+            0.5,
+            Arc::new(Metal::new(Color::new(0.8, 0.6, 0.2), 0.3)),
+        )));
+        (world, lights)
+    }
+
+    fn test_camera(thread_count: usize) -> Camera {
+        let mut cam = Camera::new();
+        cam.image_width = 24;
+        cam.aspect_ratio = 1.5;
+        cam.samples_per_pixel = 4;
+        cam.defocus_angle = 2.0;
+        cam.focus_dist = 1.0;
+        cam.thread_count = thread_count;
+        cam.seed = Some(1234);
+        cam.show_progress = false;
+        cam
+    }
+
+    #[test]
+    fn test_render_independent_of_thread_count() {
+        let (world, lights) = test_world();
+        for sampler in [SamplerType::Independent, SamplerType::Sobol] {
+            let mut serial = test_camera(1);
+            let mut parallel = test_camera(3);
+            serial.sampler = sampler;
+            parallel.sampler = sampler;
+            assert_eq!(
+                serial.render(&world, &lights).pixels(),
+                parallel.render(&world, &lights).pixels()
+            );
+        }
+    }
+
+    #[test]
+    fn test_defocus_blur_spreads_origins_over_the_disk() {
+        let mut cam = test_camera(1);
+        cam.lookfrom = Point3::new(1.0, 2.0, 3.0);
+        cam.lookat = Point3::new(0.0, 0.0, -1.0);
+        cam.defocus_angle = 10.0;
+        cam.focus_dist = 4.0;
+        cam.initialize();
+        let radius = 4.0 * degrees_to_radians(5.0).tan();
+        let mut sampler = crate::IndependentSampler::new(3);
+
+        let mut widest: f64 = 0.0;
+        for k in 0..1000 {
+            sampler.start_pixel_sample(5, 7, k);
+            let r = cam.get_ray(5, 7, &mut sampler);
+            let offset = r.origin() - cam.lookfrom;
+            // Origins stay on the lens plane, within the disk
+            assert!(crate::dot(offset, cam.w).abs() < 1e-12);
+            assert!(offset.length() <= radius + 1e-12);
+            widest = widest.max(offset.length());
+        }
+        assert!(widest > 0.9 * radius);
+
+        // A pinhole camera shoots every ray from lookfrom
+        cam.defocus_angle = 0.0;
+        cam.initialize();
+        sampler.start_pixel_sample(5, 7, 0);
+        assert_eq!(cam.get_ray(5, 7, &mut sampler).origin(), cam.lookfrom);
+    }
+
+    #[test]
+    fn test_render_pixel_matches_full_render() {
+        let (world, lights) = test_world();
+        let mut cam = test_camera(2);
+        let image = cam.render(&world, &lights);
+        for (i, j) in [(0, 0), (11, 7), (23, 15)] {
+            assert_eq!(cam.render_pixel(&world, &lights, i, j), image.pixel(i, j));
+        }
+    }
+
+    #[test]
+    fn test_adaptive_sampling_stops_on_flat_background() {
+        let mut cam = test_camera(2);
+        cam.background = Background::Solid(Color::new(0.2, 0.3, 0.4));
+        cam.adaptive = Some(AdaptiveSampling {
+            threshold: 0.05,
+            min_samples: 4,
+            max_samples: 64,
+        });
+        let image = cam.render(&HittableList::new(), &HittableList::new());
+        assert!(cam.sample_counts().iter().all(|&count| count == 4));
+        assert_eq!(image.pixel(3, 3), Color::new(0.2, 0.3, 0.4));
+    }
+
+    #[test]
+    fn test_adaptive_sampling_spends_samples_on_noise() {
+        let mut cam = test_camera(2);
+        cam.adaptive = Some(AdaptiveSampling {
+            threshold: 0.02,
+            min_samples: 4,
+            max_samples: 32,
+        });
+        let (world, lights) = test_world();
+        cam.render(&world, &lights);
+        let counts = cam.sample_counts();
+        assert_eq!(counts.len(), 24 * 16);
+        assert!(counts.iter().all(|&count| (4..=32).contains(&count)));
+        assert!(counts.contains(&32));
+        assert!(counts.iter().any(|&count| count < 32));
+
+        let heat_map = cam.sample_count_image();
+        assert!(heat_map.pixels().iter().all(|p| p.x() <= 1.0));
+    }
+
+    fn mean_luminance(image: &Framebuffer) -> f64 {
+        let total: f64 = image
+            .pixels()
+            .iter()
+            .map(|p| 0.2126 * p.x() + 0.7152 * p.y() + 0.0722 * p.z())
+            .sum();
+        total / image.pixels().len() as f64
+    }
+
+    #[test]
+    fn test_light_sampling_matches_brute_force() {
+        let (world, lights) = test_world();
+        let mut cam = test_camera(2);
+        cam.image_width = 12;
+        cam.samples_per_pixel = 1024;
+        cam.background = Background::None;
+
+        let brute_force = mean_luminance(&cam.render(&world, &HittableList::new()));
+        let with_lights = mean_luminance(&cam.render(&world, &lights));
+        assert!(
+            (brute_force - with_lights).abs() < 0.03 * brute_force,
+            "{brute_force} vs {with_lights}"
+        );
+    }
+
+    #[test]
+    fn test_light_sampling_matches_brute_force_on_partly_specular_surfaces() {
+        // Half the ground is smooth glass, which cannot use the light samples, and half diffuse,
+        // which must use them whichever lobe the bounce picks
+        let (world, lights) = test_world();
+        let mut partly_specular = HittableList::new();
+        partly_specular.add(world.objects()[0].clone());
+        partly_specular.add(Arc::new(Sphere::new(
+            Point3::new(0.0, -100.5, -1.0),
+            100.0,
+            Arc::new(
+                Principled::new(Color::new(0.8, 0.8, 0.8))
+                    .with_roughness(crate::principled::scalar(0.0))
+                    .with_transmission(crate::principled::scalar(0.5), 1.5),
+            ),
+        )));
+        let mut cam = test_camera(2);
+        cam.image_width = 12;
+        cam.samples_per_pixel = 1024;
+        cam.background = Background::None;
+
+        let brute_force = mean_luminance(&cam.render(&partly_specular, &HittableList::new()));
+        let with_lights = mean_luminance(&cam.render(&partly_specular, &lights));
+        assert!(
+            (brute_force - with_lights).abs() < 0.03 * brute_force,
+            "{brute_force} vs {with_lights}"
+        );
+    }
+
+    // Root mean square difference between two renders made with different seeds
+    fn noise(cam: &mut Camera, world: &HittableList, lights: &HittableList) -> f64 {
+        cam.seed = Some(1);
+        let a = cam.render(world, lights);
+        cam.seed = Some(2);
+        let b = cam.render(world, lights);
+        let sum: f64 = a
+            .pixels()
+            .iter()
+            .zip(b.pixels())
+            .map(|(p, q)| (*p - *q).length_squared())
+            .sum();
+        (sum / a.pixels().len() as f64).sqrt()
+    }
+
+    #[test]
+    fn test_light_sampling_reduces_noise() {
+        // Only diffuse surfaces, so every path can use the light samples
+        let (mut world, lights) = test_world();
+        let objects = world.objects().to_vec();
+        world.clear();
+        world.add(objects[0].clone());
+        world.add(objects[1].clone());
+        let mut cam = test_camera(2);
+        cam.image_width = 12;
+        cam.samples_per_pixel = 16;
+        cam.background = Background::None;
+
+        let brute_force = noise(&mut cam, &world, &HittableList::new());
+        let with_lights = noise(&mut cam, &world, &lights);
+        assert!(
+            with_lights < 0.5 * brute_force,
+            "{with_lights} vs {brute_force}"
+        );
+    }
+
+    #[test]
+    fn test_adaptive_stratified_is_no_noisier_than_independent() {
+        let (world, lights) = test_world();
+        let mut cam = test_camera(2);
+        cam.image_width = 24;
+        // Every pixel stops at min_samples, which must still cover all the strata
+        cam.adaptive = Some(AdaptiveSampling {
+            threshold: 1e9,
+            min_samples: 16,
+            max_samples: 1024,
+        });
 
-        return Ray::new(ray_origin, ray_direction);
+        cam.sampler = SamplerType::Independent;
+        let independent = noise(&mut cam, &world, &lights);
+        cam.sampler = SamplerType::Stratified;
+        let stratified = noise(&mut cam, &world, &lights);
+        assert!(stratified <= independent, "{stratified} vs {independent}");
     }
 
-    fn sample_square() -> Vec3 {
-        return Vec3::new(random_double() - 0.5, random_double() - 0.5, 0.0);
+    #[test]
+    fn test_russian_roulette_is_unbiased() {
+        let (world, lights) = test_world();
+        let mut cam = test_camera(2);
+        cam.image_width = 12;
+        cam.samples_per_pixel = 256;
+        cam.max_depth = 20;
+        cam.background = Background::Solid(Color::new(0.5, 0.5, 0.5));
+
+        cam.roulette_depth = cam.max_depth;
+        let without = mean_luminance(&cam.render(&world, &lights));
+        cam.roulette_depth = 0;
+        let with = mean_luminance(&cam.render(&world, &lights));
+        assert!(
+            (without - with).abs() < 0.02 * without,
+            "{without} vs {with}"
+        );
+    }
+
+    #[test]
+    fn test_deep_paths_do_not_overflow_the_stack() {
+        // A mirror sphere seen from inside never lets a path escape
+        let mut world = HittableList::new();
+        world.add(Arc::new(Sphere::new(
+            Point3::new(0.0, 0.0, 0.0),
+            10.0,
+            Arc::new(Metal::new(Color::new(1.0, 1.0, 1.0), 0.0)),
+        )));
+        let mut cam = test_camera(1);
+        cam.image_width = 2;
+        cam.aspect_ratio = 1.0;
+        cam.samples_per_pixel = 1;
+        cam.max_depth = 100_000;
+        cam.roulette_depth = cam.max_depth;
+
+        let image = cam.render(&world, &HittableList::new());
+        assert_eq!(image.pixel(0, 0), Color::new(0.0, 0.0, 0.0));
     }
 }
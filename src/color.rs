@@ -1,31 +1,64 @@
 use crate::{Interval, Vec3};
-use std::fs::File;
-use std::io::Write;
+use std::io::{self, Write};
 
 pub type Color = Vec3;
 
-pub fn write_color(mut file_handle: &File, pixel_color: &Color) {
+// Write one display-referred pixel as a line of a plain-text (P3) PPM image
+pub fn write_color<W: Write>(out: &mut W, pixel_color: &Color) -> io::Result<()> {
+    let [rbyte, gbyte, bbyte] = color_to_bytes(pixel_color);
+    writeln!(out, "{} {} {}", rbyte, gbyte, bbyte)
+}
+
+// sRGB-encode a linear display color and quantize it to 8 bits per channel
+pub fn color_to_bytes(pixel_color: &Color) -> [u8; 3] {
     let mut r = pixel_color.x();
     let mut g = pixel_color.y();
     let mut b = pixel_color.z();
 
-    r = linear_to_gamma(r);
-    g = linear_to_gamma(g);
-    b = linear_to_gamma(b);
+    r = linear_to_srgb(r);
+    g = linear_to_srgb(g);
+    b = linear_to_srgb(b);
 
     // Translate the [0,1] component values to the byte range [0,255]
     let intensity = Interval::new(0.000, 0.999);
-    let rbyte: i32 = (256.0 * intensity.clamp(r)) as i32;
-    let gbyte: i32 = (256.0 * intensity.clamp(g)) as i32;
-    let bbyte: i32 = (256.0 * intensity.clamp(b)) as i32;
+    let rbyte = (256.0 * intensity.clamp(r)) as u8;
+    let gbyte = (256.0 * intensity.clamp(g)) as u8;
+    let bbyte = (256.0 * intensity.clamp(b)) as u8;
+
+    [rbyte, gbyte, bbyte]
+}
+
+// Encode a linear component with the piecewise sRGB transfer function
+pub fn linear_to_srgb(linear_component: f64) -> f64 {
+    if linear_component <= 0.0 {
+        return 0.0;
+    }
+    if linear_component <= 0.0031308 {
+        12.92 * linear_component
+    } else {
+        1.055 * linear_component.powf(1.0 / 2.4) - 0.055
+    }
+}
 
-    writeln!(file_handle, "{} {} {}", rbyte, gbyte, bbyte)
-        .expect("Unable to write content to file");
+// Decode an sRGB-encoded component, as stored in 8-bit images, to linear light
+pub fn srgb_to_linear(encoded: f64) -> f64 {
+    if encoded <= 0.04045 {
+        encoded / 12.92
+    } else {
+        ((encoded + 0.055) / 1.055).powf(2.4)
+    }
 }
 
-pub fn linear_to_gamma(linear_component: f64) -> f64 {
-    if linear_component > 0.0 {
-        return linear_component.sqrt();
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_srgb_round_trip() {
+        for i in 0..=100 {
+            let x = i as f64 / 100.0;
+            assert!((srgb_to_linear(linear_to_srgb(x)) - x).abs() < 1e-12);
+        }
+        assert_eq!(linear_to_srgb(-1.0), 0.0);
     }
-    return 0.0;
 }
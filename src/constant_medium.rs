@@ -0,0 +1,166 @@
+use crate::{
+    utility::ray_rng, Aabb, Color, HitRecord, Hittable, Interval, Isotropic, Material, Ray,
+    Texture, Vec3, INFINITY,
+};
+use std::sync::Arc;
+
+// Fog or smoke of uniform density filling a closed boundary. A ray passing through scatters
+// after an exponentially distributed distance, or leaves without touching anything.
+pub struct ConstantMedium {
+    boundary: Arc<dyn Hittable>,
+    density: f64,
+    phase_function: Arc<dyn Material>,
+}
+
+impl ConstantMedium {
+    pub fn new(boundary: Arc<dyn Hittable>, density: f64, albedo: Color) -> Self {
+        Self::from_material(boundary, density, Arc::new(Isotropic::new(albedo)))
+    }
+
+    pub fn from_texture(boundary: Arc<dyn Hittable>, density: f64, tex: Arc<dyn Texture>) -> Self {
+        Self::from_material(boundary, density, Arc::new(Isotropic::from_texture(tex)))
+    }
+
+    pub fn from_material(
+        boundary: Arc<dyn Hittable>,
+        density: f64,
+        phase_function: Arc<dyn Material>,
+    ) -> Self {
+        Self {
+            boundary,
+            density,
+            phase_function,
+        }
+    }
+
+    // Where r is inside the boundary during ray_t, or None if it misses. The boundary must be
+    // closed; a ray starting inside travels through the medium from its origin.
+    fn inside_range(&self, r: &Ray, ray_t: Interval) -> Option<Interval> {
+        let mut rec1 = HitRecord::new();
+        let mut rec2 = HitRecord::new();
+        if !self
+            .boundary
+            .hit_interval(r, Interval::new(-INFINITY, INFINITY), &mut rec1)
+        {
+            return None;
+        }
+        if !self
+            .boundary
+            .hit_interval(r, Interval::new(rec1.t + 0.0001, INFINITY), &mut rec2)
+        {
+            return None;
+        }
+
+        let inside = Interval::new(f64::max(rec1.t, ray_t.min), f64::min(rec2.t, ray_t.max));
+        (inside.min < inside.max).then_some(inside)
+    }
+}
+
+impl Hittable for ConstantMedium {
+    fn hit_tmin_tmax(&self, r: &Ray, ray_tmin: f64, ray_tmax: f64, rec: &mut HitRecord) -> bool {
+        self.hit_interval(r, Interval::new(ray_tmin, ray_tmax), rec)
+    }
+
+    // Hit tests get no sampler, so the free-flight distance is drawn from the ray's own
+    // generator. The same ray scatters at the same place however often the BVH asks.
+    fn hit_interval(&self, r: &Ray, ray_t: Interval, rec: &mut HitRecord) -> bool {
+        let Some(inside) = self.inside_range(r, ray_t) else {
+            return false;
+        };
+
+        let ray_length = r.direction().length();
+        let distance_inside_boundary = inside.size() * ray_length;
+        let hit_distance = -(1.0 - ray_rng(r).random_double()).ln() / self.density;
+        if hit_distance > distance_inside_boundary {
+            return false;
+        }
+
+        rec.t = inside.min + hit_distance / ray_length;
+        rec.p = r.at(rec.t);
+        rec.normal = Vec3::new(1.0, 0.0, 0.0); // Arbitrary; the phase function ignores it
+        rec.tangent = Vec3::new(0.0, 0.0, 0.0);
+        rec.front_face = true;
+        rec.u = 0.0;
+        rec.v = 0.0;
+        rec.mat = self.phase_function.clone();
+
+        true
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.boundary.bounding_box()
+    }
+
+    fn has_media(&self) -> bool {
+        true
+    }
+
+    fn hit_surface(&self, _r: &Ray, _ray_t: Interval, _rec: &mut HitRecord) -> bool {
+        false
+    }
+
+    // Beer-Lambert law
+    fn transmittance(&self, r: &Ray, ray_t: Interval) -> f64 {
+        match self.inside_range(r, ray_t) {
+            Some(inside) => (-self.density * inside.size() * r.direction().length()).exp(),
+            None => 1.0,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{make_box, DefaultMaterial, Point3, Sphere};
+
+    #[test]
+    fn test_transmittance_follows_beer_lambert() {
+        let boundary = Arc::new(make_box(
+            Point3::new(-1.0, -1.0, -1.0),
+            Point3::new(1.0, 1.0, 1.0),
+            Arc::new(DefaultMaterial::new()),
+        ));
+        let density = 0.5;
+        let medium = ConstantMedium::new(boundary, density, Color::new(1.0, 1.0, 1.0));
+
+        // Rays crossing 2 units of medium pass through with probability exp(-density * 2)
+        let count = 20000;
+        let mut passed = 0;
+        for k in 0..count {
+            let y = -0.9 + 1.8 * (k as f64 + 0.5) / count as f64;
+            let r = Ray::new(Point3::new(-5.0, y, 0.3), Vec3::new(1.0, 0.0, 0.0));
+            let mut rec = HitRecord::new();
+            if !medium.hit_interval(&r, Interval::new(0.001, INFINITY), &mut rec) {
+                passed += 1;
+            } else {
+                assert!(rec.t >= 4.0 && rec.t <= 6.0);
+            }
+        }
+        let expected = (-density * 2.0).exp();
+        assert!((passed as f64 / count as f64 - expected).abs() < 0.02);
+
+        let r = Ray::new(Point3::new(-5.0, 0.0, 0.0), Vec3::new(2.0, 0.0, 0.0));
+        let t = medium.transmittance(&r, Interval::new(0.001, 2.5));
+        assert!((t - (-density).exp()).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_hits_are_repeatable_and_start_inside() {
+        let boundary = Arc::new(Sphere::new(
+            Point3::new(0.0, 0.0, 0.0),
+            1.0,
+            Arc::new(DefaultMaterial::new()),
+        ));
+        let medium = ConstantMedium::new(boundary, 100.0, Color::new(1.0, 1.0, 1.0));
+
+        // A ray from the center scatters shortly after leaving its origin
+        let r = Ray::new(Point3::new(0.0, 0.0, 0.0), Vec3::new(0.0, 2.0, 0.0));
+        let mut first = HitRecord::new();
+        assert!(medium.hit_interval(&r, Interval::new(0.001, INFINITY), &mut first));
+        assert!(first.t > 0.001 && first.t < 0.5);
+
+        let mut second = HitRecord::new();
+        assert!(medium.hit_interval(&r, Interval::new(0.001, INFINITY), &mut second));
+        assert_eq!(first.t, second.t);
+    }
+}
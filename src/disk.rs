@@ -0,0 +1,154 @@
+use crate::{
+    dot, unit_vector, vec3::Onb, Aabb, HitRecord, Hittable, Interval, Material, Point3, Ray,
+    Sampler, ShapeSample, Vec3, INFINITY, PI,
+};
+use std::sync::Arc;
+
+// Flat disk facing along its normal. Texture coordinates map the disk onto the unit square,
+// with the center at (0.5, 0.5).
+pub struct Disk {
+    center: Point3,
+    normal: Vec3,
+    radius: f64,
+    frame: Onb, // w is the normal; u and v span the disk
+    mat: Arc<dyn Material>,
+    bbox: Aabb,
+}
+
+impl Disk {
+    pub fn new(center: Point3, normal: Vec3, radius: f64, mat: Arc<dyn Material>) -> Self {
+        let normal = unit_vector(normal);
+        let radius = f64::max(0.0, radius);
+
+        // Extent of the disk rim along each axis
+        let extent = Vec3::new(
+            radius * (1.0 - normal.x() * normal.x()).max(0.0).sqrt(),
+            radius * (1.0 - normal.y() * normal.y()).max(0.0).sqrt(),
+            radius * (1.0 - normal.z() * normal.z()).max(0.0).sqrt(),
+        );
+
+        Self {
+            center,
+            normal,
+            radius,
+            frame: Onb::new(normal),
+            mat,
+            bbox: Aabb::from_points(center - extent, center + extent),
+        }
+    }
+}
+
+impl Hittable for Disk {
+    fn hit_tmin_tmax(&self, r: &Ray, ray_tmin: f64, ray_tmax: f64, rec: &mut HitRecord) -> bool {
+        self.hit_interval(r, Interval::new(ray_tmin, ray_tmax), rec)
+    }
+
+    fn hit_interval(&self, r: &Ray, ray_t: Interval, rec: &mut HitRecord) -> bool {
+        let denom = dot(self.normal, r.direction());
+
+        // The ray is parallel to the disk
+        if denom.abs() < 1e-8 {
+            return false;
+        }
+
+        let t = dot(self.normal, self.center - r.origin()) / denom;
+        if !ray_t.surrounds(t) {
+            return false;
+        }
+
+        let p = r.at(t);
+        let offset = p - self.center;
+        if offset.length_squared() > self.radius * self.radius {
+            return false;
+        }
+
+        rec.t = t;
+        rec.p = p;
+        rec.u = 0.5 + dot(offset, self.frame.u()) / (2.0 * self.radius);
+        rec.v = 0.5 + dot(offset, self.frame.v()) / (2.0 * self.radius);
+        rec.tangent = self.frame.u();
+        rec.mat = self.mat.clone();
+        rec.set_face_normal(r, &self.normal);
+
+        true
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.bbox
+    }
+
+    // Uniformly sample the area of the disk
+    fn sample(&self, origin: &Point3, time: f64, sampler: &mut dyn Sampler) -> Option<ShapeSample> {
+        let (r1, r2) = sampler.get_2d();
+        let r = self.radius * r1.sqrt();
+        let phi = 2.0 * PI * r2;
+        let p = self.center
+            + self
+                .frame
+                .transform(Vec3::new(r * phi.cos(), r * phi.sin(), 0.0));
+        let pdf = self.pdf_value(&Ray::new_with_time(*origin, p - *origin, time));
+        (pdf > 0.0).then_some(ShapeSample { p, pdf })
+    }
+
+    fn pdf_value(&self, r: &Ray) -> f64 {
+        let mut rec = HitRecord::new();
+        if !self.hit_interval(r, Interval::new(0.001, INFINITY), &mut rec) {
+            return 0.0;
+        }
+
+        // Convert the area density to a density over solid angle
+        let area = PI * self.radius * self.radius;
+        let distance_squared = rec.t * rec.t * r.direction().length_squared();
+        let cosine = dot(r.direction(), self.normal).abs() / r.direction().length();
+        if cosine < 1e-8 {
+            return 0.0;
+        }
+        distance_squared / (cosine * area)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{DefaultMaterial, IndependentSampler};
+
+    fn disk() -> Disk {
+        Disk::new(
+            Point3::new(0.0, 0.0, -2.0),
+            Vec3::new(0.0, 0.0, 1.0),
+            1.0,
+            Arc::new(DefaultMaterial::new()),
+        )
+    }
+
+    #[test]
+    fn test_hit_inside_radius_only() {
+        let disk = disk();
+        let mut rec = HitRecord::new();
+        let center = Ray::new(Point3::new(0.0, 0.0, 0.0), Vec3::new(0.0, 0.0, -1.0));
+        assert!(disk.hit_interval(&center, Interval::new(0.001, INFINITY), &mut rec));
+        assert!((rec.u - 0.5).abs() < 1e-12 && (rec.v - 0.5).abs() < 1e-12);
+        assert!(rec.front_face);
+
+        let rim = Ray::new(Point3::new(0.9, 0.0, 0.0), Vec3::new(0.0, 0.0, -1.0));
+        assert!(disk.hit_interval(&rim, Interval::new(0.001, INFINITY), &mut rec));
+        assert!((0.0..=1.0).contains(&rec.u) && (0.0..=1.0).contains(&rec.v));
+
+        let outside = Ray::new(Point3::new(0.8, 0.8, 0.0), Vec3::new(0.0, 0.0, -1.0));
+        assert!(!disk.hit_interval(&outside, Interval::new(0.001, INFINITY), &mut rec));
+    }
+
+    #[test]
+    fn test_sample_lies_on_disk() {
+        let disk = disk();
+        let origin = Point3::new(0.3, 0.5, 1.0);
+        let mut sampler = IndependentSampler::new(2);
+        for _ in 0..100 {
+            let sample = disk.sample(&origin, 0.0, &mut sampler).unwrap();
+            assert!((sample.p.z() + 2.0).abs() < 1e-12);
+            assert!((sample.p - disk.center).length() <= 1.0 + 1e-12);
+            let r = Ray::new(origin, sample.p - origin);
+            assert!((disk.pdf_value(&r) - sample.pdf).abs() < 1e-9 * sample.pdf);
+        }
+    }
+}
@@ -1,12 +1,18 @@
-use crate::{dot, interval::Interval, DefaultMaterial, Material, Point3, Ray, Vec3};
-use std::rc::Rc;
+use crate::{
+    dot, interval::Interval, vec3::Onb, Aabb, DefaultMaterial, Material, Point3, Ray, Sampler, Vec3,
+};
+use std::sync::Arc;
 
 #[derive(Clone)]
 pub struct HitRecord {
     pub p: Point3,
     pub normal: Vec3,
-    pub mat: Rc<dyn Material>,
+    pub mat: Arc<dyn Material>,
     pub t: f64,
+    pub u: f64,            // Surface texture coordinate
+    pub v: f64,            // Surface texture coordinate
+    pub barycentric: Vec3, // Barycentric weights of the hit point on a triangle
+    pub tangent: Vec3,     // Direction of increasing u (dp/du), or zero when the shape has none
     pub front_face: bool,
 }
 
@@ -16,7 +22,11 @@ impl HitRecord {
             p: Point3::new(0.0, 0.0, 0.0),
             normal: Vec3::new(0.0, 0.0, 0.0),
             t: 0.0,
-            mat: Rc::new(DefaultMaterial::new()),
+            u: 0.0,
+            v: 0.0,
+            barycentric: Vec3::new(0.0, 0.0, 0.0),
+            tangent: Vec3::new(0.0, 0.0, 0.0),
+            mat: Arc::new(DefaultMaterial::new()),
             front_face: false,
         }
     }
@@ -31,30 +41,103 @@ impl HitRecord {
             -(*outward_normal)
         };
     }
+
+    // Local frame for BSDFs: the normal as w and, where the shape provides one, the tangent
+    // as u, so that anisotropic materials follow the surface
+    pub fn shading_frame(&self) -> Onb {
+        Onb::from_normal_tangent(self.normal, self.tangent)
+    }
+}
+
+impl Default for HitRecord {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// A point picked on a shape for light sampling
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ShapeSample {
+    pub p: Point3, // Sampled point on the surface
+    pub pdf: f64,  // Density of the direction towards p, per unit solid angle
 }
 
-pub trait Hittable {
+// Scenes are shared by every render thread, so hittables must be Send + Sync
+pub trait Hittable: Send + Sync {
     fn hit_tmin_tmax(&self, r: &Ray, ray_tmin: f64, ray_tmax: f64, rec: &mut HitRecord) -> bool;
     fn hit_interval(&self, r: &Ray, ray_t: Interval, rec: &mut HitRecord) -> bool;
+    fn bounding_box(&self) -> Aabb;
+
+    // Pick a point on the shape as seen from origin at the given time. Shapes that cannot be
+    // sampled, or not from this origin, return None.
+    fn sample(
+        &self,
+        _origin: &Point3,
+        _time: f64,
+        _sampler: &mut dyn Sampler,
+    ) -> Option<ShapeSample> {
+        None
+    }
+
+    // Solid angle density with which sample would pick the direction of r, seen from its
+    // origin at its time. Zero wherever sample cannot go.
+    fn pdf_value(&self, _r: &Ray) -> f64 {
+        0.0
+    }
+
+    // Whether this is, or contains, a participating medium
+    fn has_media(&self) -> bool {
+        false
+    }
+
+    // Like hit_interval, but rays pass through participating media. Shadow rays use this and
+    // account for the media with transmittance.
+    fn hit_surface(&self, r: &Ray, ray_t: Interval, rec: &mut HitRecord) -> bool {
+        self.hit_interval(r, ray_t, rec)
+    }
+
+    // Fraction of the light that makes it through the media along r within ray_t. Surfaces
+    // are ignored. Random media may return an unbiased estimate rather than the exact value.
+    fn transmittance(&self, _r: &Ray, _ray_t: Interval) -> f64 {
+        1.0
+    }
 }
 
 pub struct HittableList {
-    objects: Vec<Rc<dyn Hittable>>,
+    objects: Vec<Arc<dyn Hittable>>,
+    bbox: Aabb,
+    has_media: bool,
 }
 
 impl HittableList {
     pub fn new() -> Self {
         HittableList {
             objects: Vec::new(),
+            bbox: Aabb::empty(),
+            has_media: false,
         }
     }
 
-    pub fn add(&mut self, object: Rc<dyn Hittable>) {
+    pub fn add(&mut self, object: Arc<dyn Hittable>) {
+        self.bbox = Aabb::surrounding(&self.bbox, &object.bounding_box());
+        self.has_media |= object.has_media();
         self.objects.push(object);
     }
 
     pub fn clear(&mut self) {
         self.objects.clear();
+        self.bbox = Aabb::empty();
+        self.has_media = false;
+    }
+
+    pub fn objects(&self) -> &[Arc<dyn Hittable>] {
+        &self.objects
+    }
+}
+
+impl Default for HittableList {
+    fn default() -> Self {
+        Self::new()
     }
 }
 
@@ -62,13 +145,7 @@ impl Hittable for HittableList {
     fn hit_tmin_tmax(&self, r: &Ray, ray_tmin: f64, ray_tmax: f64, rec: &mut HitRecord) -> bool {
         let mut hit_anything = false;
         let mut closest_so_far = ray_tmax;
-        let mut temp_rec = HitRecord {
-            p: Point3::new(0.0, 0.0, 0.0),
-            normal: Vec3::new(0.0, 0.0, 0.0),
-            mat: Rc::new(DefaultMaterial::new()),
-            t: 0.0,
-            front_face: false,
-        };
+        let mut temp_rec = HitRecord::new();
 
         for object in &self.objects {
             if object.hit_tmin_tmax(r, ray_tmin, closest_so_far, &mut temp_rec) {
@@ -78,18 +155,12 @@ impl Hittable for HittableList {
             }
         }
 
-        return hit_anything;
+        hit_anything
     }
     fn hit_interval(&self, r: &Ray, ray_t: Interval, rec: &mut HitRecord) -> bool {
         let mut hit_anything = false;
         let mut closest_so_far = ray_t.max;
-        let mut temp_rec = HitRecord {
-            p: Point3::new(0.0, 0.0, 0.0),
-            normal: Vec3::new(0.0, 0.0, 0.0),
-            mat: Rc::new(DefaultMaterial::new()),
-            t: 0.0,
-            front_face: false,
-        };
+        let mut temp_rec = HitRecord::new();
 
         for object in &self.objects {
             if object.hit_interval(r, Interval::new(ray_t.min, closest_so_far), &mut temp_rec) {
@@ -98,6 +169,94 @@ impl Hittable for HittableList {
                 *rec = temp_rec.clone();
             }
         }
-        return hit_anything;
+        hit_anything
     }
+
+    fn bounding_box(&self) -> Aabb {
+        self.bbox
+    }
+
+    fn has_media(&self) -> bool {
+        self.has_media
+    }
+
+    fn hit_surface(&self, r: &Ray, ray_t: Interval, rec: &mut HitRecord) -> bool {
+        if !self.has_media {
+            return self.hit_interval(r, ray_t, rec);
+        }
+
+        let mut hit_anything = false;
+        let mut closest_so_far = ray_t.max;
+        let mut temp_rec = HitRecord::new();
+        for object in &self.objects {
+            if object.hit_surface(r, Interval::new(ray_t.min, closest_so_far), &mut temp_rec) {
+                hit_anything = true;
+                closest_so_far = temp_rec.t;
+                *rec = temp_rec.clone();
+            }
+        }
+        hit_anything
+    }
+
+    fn transmittance(&self, r: &Ray, ray_t: Interval) -> f64 {
+        if !self.has_media {
+            return 1.0;
+        }
+        self.objects
+            .iter()
+            .filter(|object| object.has_media())
+            .map(|object| object.transmittance(r, ray_t))
+            .product()
+    }
+
+    // Sample one object picked uniformly. The density is that of the whole list, so
+    // directions several objects can produce are weighted correctly.
+    fn sample(&self, origin: &Point3, time: f64, sampler: &mut dyn Sampler) -> Option<ShapeSample> {
+        if self.objects.is_empty() {
+            return None;
+        }
+        let count = self.objects.len();
+        let index = ((sampler.get_1d() * count as f64) as usize).min(count - 1);
+        let sample = self.objects[index].sample(origin, time, sampler)?;
+        let pdf = self.pdf_value(&Ray::new_with_time(*origin, sample.p - *origin, time));
+        Some(ShapeSample { p: sample.p, pdf })
+    }
+
+    fn pdf_value(&self, r: &Ray) -> f64 {
+        if self.objects.is_empty() {
+            return 0.0;
+        }
+        let total: f64 = self.objects.iter().map(|object| object.pdf_value(r)).sum();
+        total / self.objects.len() as f64
+    }
+}
+
+// Check that object's light sampling agrees with the solid angle it covers from origin: the mean
+// of 1 / pdf over samples and the fraction of uniform directions that hit it both estimate it
+#[cfg(test)]
+pub(crate) fn assert_sample_density_matches_solid_angle(
+    object: &dyn Hittable,
+    origin: Point3,
+    sampler: &mut dyn Sampler,
+) {
+    let count = 20000;
+    let mut inverse_pdf_sum = 0.0;
+    for _ in 0..count {
+        let sample = object.sample(&origin, 0.0, sampler).unwrap();
+        let r = Ray::new(origin, sample.p - origin);
+        assert!((object.pdf_value(&r) - sample.pdf).abs() < 1e-9 * sample.pdf);
+        inverse_pdf_sum += 1.0 / sample.pdf;
+    }
+    let from_samples = inverse_pdf_sum / count as f64;
+
+    let mut hits = 0;
+    for _ in 0..count {
+        let r = Ray::new(origin, crate::vec3::random_unit_vector(sampler));
+        if object.pdf_value(&r) > 0.0 {
+            hits += 1;
+        }
+    }
+    let from_directions = 4.0 * crate::PI * hits as f64 / count as f64;
+
+    assert!((from_samples - from_directions).abs() < 0.05 * from_directions);
 }
@@ -0,0 +1,184 @@
+use crate::{
+    unit_vector, Aabb, HitRecord, Hittable, Interval, Mat4, Point3, Ray, Sampler, ShapeSample,
+    INFINITY,
+};
+use std::sync::Arc;
+
+// A hittable placed in the world by an affine transform. Rays are moved into object space
+// rather than the object into the world, so many instances can share one object.
+pub struct Instance {
+    object: Arc<dyn Hittable>,
+    to_world: Mat4,
+    to_object: Mat4,
+    bbox: Aabb,
+}
+
+impl Instance {
+    // Returns None if the transform is not invertible
+    pub fn new(object: Arc<dyn Hittable>, to_world: Mat4) -> Option<Self> {
+        let to_object = to_world.inverse()?;
+
+        // Box around the transformed corners of the object's box
+        let object_box = object.bounding_box();
+        let mut min = Point3::new(INFINITY, INFINITY, INFINITY);
+        let mut max = Point3::new(-INFINITY, -INFINITY, -INFINITY);
+        for corner in 0..8 {
+            let pick = |axis: usize| {
+                let interval = object_box.axis_interval(axis);
+                if corner & (1 << axis) == 0 {
+                    interval.min
+                } else {
+                    interval.max
+                }
+            };
+            let p = to_world.transform_point(Point3::new(pick(0), pick(1), pick(2)));
+            for axis in 0..3 {
+                min[axis] = min[axis].min(p[axis]);
+                max[axis] = max[axis].max(p[axis]);
+            }
+        }
+
+        Some(Self {
+            object,
+            to_world,
+            to_object,
+            bbox: Aabb::from_points(min, max),
+        })
+    }
+
+    pub fn transform(&self) -> Mat4 {
+        self.to_world
+    }
+
+    // The direction is left unnormalized so that t means the same in both spaces
+    fn to_object_ray(&self, r: &Ray) -> Ray {
+        Ray::new_with_time(
+            self.to_object.transform_point(r.origin()),
+            self.to_object.transform_vector(r.direction()),
+            r.time(),
+        )
+    }
+
+    // Move a hit found in object space back out. The inverse transpose keeps the sign of the
+    // normal against the ray direction, so front_face carries over unchanged.
+    fn to_world_record(&self, rec: &mut HitRecord) {
+        rec.p = self.to_world.transform_point(rec.p);
+        rec.normal = unit_vector(self.to_object.transform_normal(rec.normal));
+        rec.tangent = self.to_world.transform_vector(rec.tangent);
+    }
+}
+
+impl Hittable for Instance {
+    fn hit_tmin_tmax(&self, r: &Ray, ray_tmin: f64, ray_tmax: f64, rec: &mut HitRecord) -> bool {
+        self.hit_interval(r, Interval::new(ray_tmin, ray_tmax), rec)
+    }
+
+    fn hit_interval(&self, r: &Ray, ray_t: Interval, rec: &mut HitRecord) -> bool {
+        if !self.object.hit_interval(&self.to_object_ray(r), ray_t, rec) {
+            return false;
+        }
+        self.to_world_record(rec);
+        true
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.bbox
+    }
+
+    fn has_media(&self) -> bool {
+        self.object.has_media()
+    }
+
+    fn hit_surface(&self, r: &Ray, ray_t: Interval, rec: &mut HitRecord) -> bool {
+        if !self.object.hit_surface(&self.to_object_ray(r), ray_t, rec) {
+            return false;
+        }
+        self.to_world_record(rec);
+        true
+    }
+
+    fn transmittance(&self, r: &Ray, ray_t: Interval) -> f64 {
+        self.object.transmittance(&self.to_object_ray(r), ray_t)
+    }
+
+    fn sample(&self, origin: &Point3, time: f64, sampler: &mut dyn Sampler) -> Option<ShapeSample> {
+        let object_origin = self.to_object.transform_point(*origin);
+        let sample = self.object.sample(&object_origin, time, sampler)?;
+        let p = self.to_world.transform_point(sample.p);
+        let pdf = self.pdf_value(&Ray::new_with_time(*origin, p - *origin, time));
+        (pdf > 0.0).then_some(ShapeSample { p, pdf })
+    }
+
+    fn pdf_value(&self, r: &Ray) -> f64 {
+        let object_ray = self.to_object_ray(r);
+        let pdf = self.object.pdf_value(&object_ray);
+        if pdf == 0.0 {
+            return 0.0;
+        }
+
+        // Directions map through the linear part A of the transform. For a unit world
+        // direction w, the object density scales by 1 / (|det A| |A^-1 w|^3).
+        let stretch = object_ray.direction().length() / r.direction().length();
+        pdf / (self.to_world.determinant3().abs() * stretch.powi(3))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        hittable::assert_sample_density_matches_solid_angle, DefaultMaterial, IndependentSampler,
+        Sphere, Vec3,
+    };
+
+    fn unit_sphere() -> Arc<dyn Hittable> {
+        Arc::new(Sphere::new(
+            Point3::new(0.0, 0.0, 0.0),
+            1.0,
+            Arc::new(DefaultMaterial::new()),
+        ))
+    }
+
+    #[test]
+    fn test_hit_in_world_space() {
+        let to_world = Mat4::translate(Vec3::new(0.0, 0.0, -5.0))
+            * Mat4::scale(Vec3::new(1.0, 1.0, 2.0))
+            * Mat4::rotate(Vec3::new(0.0, 1.0, 0.0), 90.0);
+        let instance = Instance::new(unit_sphere(), to_world).unwrap();
+
+        let bbox = instance.bounding_box();
+        assert!((bbox.axis_interval(2).min + 7.0).abs() < 1e-9);
+        assert!((bbox.axis_interval(2).max + 3.0).abs() < 1e-9);
+
+        // The ellipsoid reaches z = -3 on its axis
+        let r = Ray::new(Point3::new(0.0, 0.0, 0.0), Vec3::new(0.0, 0.0, -1.0));
+        let mut rec = HitRecord::new();
+        assert!(instance.hit_interval(&r, Interval::new(0.001, INFINITY), &mut rec));
+        assert!((rec.t - 3.0).abs() < 1e-9);
+        assert!((rec.p - Point3::new(0.0, 0.0, -3.0)).near_zero());
+        assert!((rec.normal - Vec3::new(0.0, 0.0, 1.0)).near_zero());
+        assert!(rec.front_face);
+
+        // Off axis, the normal is the stretched sphere's, not the sphere's
+        let r = Ray::new(Point3::new(0.5, 0.0, 0.0), Vec3::new(0.0, 0.0, -1.0));
+        assert!(instance.hit_interval(&r, Interval::new(0.001, INFINITY), &mut rec));
+        let expected = unit_vector(Vec3::new(rec.p.x(), 0.0, (rec.p.z() + 5.0) / 4.0));
+        assert!((rec.normal - expected).near_zero());
+
+        assert!(Instance::new(unit_sphere(), Mat4::scale(Vec3::new(0.0, 1.0, 1.0))).is_none());
+    }
+
+    #[test]
+    fn test_sample_density_matches_solid_angle() {
+        // Non-uniform scale changes the solid angle density, which pdf_value must account for
+        let to_world =
+            Mat4::translate(Vec3::new(0.0, 1.0, -4.0)) * Mat4::scale(Vec3::new(2.0, 0.5, 1.0));
+        let instance = Instance::new(unit_sphere(), to_world).unwrap();
+        let mut sampler = IndependentSampler::new(5);
+        assert_sample_density_matches_solid_angle(
+            &instance,
+            Point3::new(0.0, 0.0, 0.0),
+            &mut sampler,
+        );
+    }
+}
@@ -1,5 +1,6 @@
 use crate::INFINITY;
 
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub struct Interval {
     pub min: f64,
     pub max: f64,
@@ -19,6 +20,22 @@ impl Interval {
         Self { min, max }
     }
 
+    // An interval that contains nothing; the identity for enclosing
+    pub fn empty() -> Self {
+        Self {
+            min: INFINITY,
+            max: -INFINITY,
+        }
+    }
+
+    // The tightest interval containing both a and b
+    pub fn enclosing(a: &Interval, b: &Interval) -> Self {
+        Self {
+            min: f64::min(a.min, b.min),
+            max: f64::max(a.max, b.max),
+        }
+    }
+
     pub fn contains(&self, t: f64) -> bool {
         t >= self.min && t <= self.max
     }
@@ -38,6 +55,11 @@ impl Interval {
         if x > self.max {
             return self.max;
         }
-        return x;
+        x
+    }
+
+    pub fn expand(&self, delta: f64) -> Interval {
+        let padding = delta / 2.0;
+        Interval::new(self.min - padding, self.max + padding)
     }
 }
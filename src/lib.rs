@@ -1,22 +1,76 @@
+mod aabb;
+mod background;
+mod bvh;
 mod camera;
 mod color;
+mod constant_medium;
 mod constants;
+mod disk;
+mod framebuffer;
+mod grid_medium;
 mod hittable;
+mod instance;
 mod interval;
+mod mat4;
 mod material;
+mod mesh;
+mod microfacet;
+mod obj;
+mod output;
+mod perlin;
+mod plane;
+mod principled;
+mod quad;
 mod ray;
+mod sampler;
+mod scene;
 mod sphere;
+mod texture;
+mod tonemap;
+mod triangle;
 mod utility;
 mod vec3;
+mod volume;
 
-pub use camera::Camera;
-pub use color::write_color;
+pub use aabb::Aabb;
+pub use background::Background;
+pub use bvh::{BvhNode, BvhStats, SplitStrategy};
+pub use camera::{AdaptiveSampling, Camera};
 pub use color::Color;
+pub use color::{color_to_bytes, linear_to_srgb, srgb_to_linear, write_color};
+pub use constant_medium::ConstantMedium;
 pub use constants::{degrees_to_radians, INFINITY, PI};
-pub use hittable::{HitRecord, Hittable, HittableList};
+pub use disk::Disk;
+pub use framebuffer::Framebuffer;
+pub use grid_medium::GridMedium;
+pub use hittable::{HitRecord, Hittable, HittableList, ShapeSample};
+pub use instance::Instance;
 pub use interval::Interval;
-pub use material::{DefaultMaterial, Dielectric, Lambertian, Material, Metal};
+pub use mat4::Mat4;
+pub use material::{
+    BsdfSample, Conductor, ConductorPreset, DefaultMaterial, Dielectric, DiffuseLight,
+    HenyeyGreenstein, Isotropic, Lambertian, Material, Metal,
+};
+pub use mesh::TriangleMesh;
+pub use microfacet::{fresnel_conductor, fresnel_dielectric, TrowbridgeReitz};
+pub use obj::{load_mtl, load_obj, ObjError};
+pub use output::{
+    save, save_image, save_ppm, to_rgb32f, to_rgb8, write_hdr, write_pfm, write_ppm, OutputError,
+    OutputFormat,
+};
+pub use perlin::Perlin;
+pub use plane::Plane;
+pub use principled::Principled;
+pub use quad::{make_box, Quad};
 pub use ray::Ray;
+pub use sampler::{
+    HaltonSampler, IndependentSampler, Sampler, SamplerType, SobolSampler, StratifiedSampler,
+};
+pub use scene::{load_scene, Scene, SceneError};
 pub use sphere::Sphere;
-pub use utility::{random_double, random_double_range};
-pub use vec3::{dot, unit_vector, Point3, Vec3};
+pub use texture::{CheckerTexture, ImageTexture, NoiseTexture, SolidColor, Texture};
+pub use tonemap::{ToneMapOperator, ToneMapper};
+pub use triangle::Triangle;
+pub use utility::Rng;
+pub use vec3::{dot, random_on_hemisphere, unit_vector, Onb, Point3, Vec3};
+pub use volume::{load_raw, load_vol, DensityGrid, VolumeError};
@@ -1,65 +1,75 @@
-use pathtracer::Camera;
-use pathtracer::Color;
-use pathtracer::Dielectric;
-use pathtracer::HittableList;
-use pathtracer::Lambertian;
-use pathtracer::Metal;
-use pathtracer::Point3;
-use pathtracer::Sphere;
-use pathtracer::Vec3;
-use pathtracer::PI;
+use pathtracer::load_scene;
+use pathtracer::save;
+use pathtracer::BvhNode;
+use pathtracer::OutputFormat;
+use pathtracer::SplitStrategy;
+use pathtracer::ToneMapper;
 
-use std::rc::Rc;
+use std::env;
+use std::process;
 
 fn main() {
-    let R = f64::cos(PI / 4.0);
+    let mut args: Vec<String> = env::args().collect();
 
-    let material_ground = Rc::new(Lambertian::new(Color::new(0.8, 0.8, 0.0)));
-    let material_center = Rc::new(Lambertian::new(Color::new(0.1, 0.2, 0.5)));
-    let material_left = Rc::new(Dielectric::new(1.50));
-    let material_bubble = Rc::new(Dielectric::new(1.00 / 1.50));
-    let material_right = Rc::new(Metal::new(Color::new(0.8, 0.6, 0.2), 1.0));
+    // --sample-counts <image> also saves how many samples each pixel took
+    let mut sample_counts_path = None;
+    if let Some(index) = args.iter().position(|arg| arg == "--sample-counts") {
+        if index + 1 >= args.len() {
+            usage();
+        }
+        sample_counts_path = Some(args.remove(index + 1));
+        args.remove(index);
+    }
 
-    let mut world = HittableList::new();
+    if args.len() < 2 || args.len() > 3 {
+        usage();
+    }
+    let scene_path = &args[1];
+    let output_path = args.get(2).map(String::as_str).unwrap_or("output.jpg");
+    let Some(format) = OutputFormat::from_path(output_path) else {
+        eprintln!("error: {}: unsupported output format", output_path);
+        process::exit(2);
+    };
 
-    world.add(Rc::new(Sphere::new(
-        Point3::new(0.0, -100.5, -1.0),
-        100.0,
-        material_ground,
-    )));
-    world.add(Rc::new(Sphere::new(
-        Point3::new(0.0, 0.0, -1.2),
-        0.5,
-        material_center,
-    )));
-    world.add(Rc::new(Sphere::new(
-        Point3::new(-1.0, 0.0, -1.0),
-        0.5,
-        material_left,
-    )));
-    world.add(Rc::new(Sphere::new(
-        Point3::new(-1.0, 0.0, -1.0),
-        0.4,
-        material_bubble,
-    )));
-    world.add(Rc::new(Sphere::new(
-        Point3::new(1.0, 0.0, -1.0),
-        0.5,
-        material_right,
-    )));
+    let scene = match load_scene(scene_path) {
+        Ok(scene) => scene,
+        Err(err) => {
+            eprintln!("error: {}", err);
+            process::exit(1);
+        }
+    };
 
-    let mut cam = Camera::new();
+    let world = BvhNode::new(scene.world, SplitStrategy::Sah);
 
-    cam.aspect_ratio = 16.0 / 9.0;
-    cam.image_width = 400;
-    cam.samples_per_pixel = 100;
-    cam.max_depth = 50;
-    cam.fvov = 90.0;
+    let mut cam = scene.camera;
+    let framebuffer = cam.render(&world, &scene.lights);
 
-    cam.lookfrom = Point3::new(-2.0,2.0,1.0);
-    cam.lookat   = Point3::new(0.0,0.0,-1.0);
-    cam.vup      = Vec3::new(0.0,1.0,0.0);
+    if let Err(err) = save(&framebuffer, output_path, format, &scene.tone_mapper) {
+        eprintln!("error: {}: {}", output_path, err);
+        process::exit(1);
+    }
 
+    if let Some(path) = sample_counts_path {
+        let Some(format) = OutputFormat::from_path(&path) else {
+            eprintln!("error: {}: unsupported output format", path);
+            process::exit(2);
+        };
+        if let Err(err) = save(
+            &cam.sample_count_image(),
+            &path,
+            format,
+            &ToneMapper::default(),
+        ) {
+            eprintln!("error: {}: {}", path, err);
+            process::exit(1);
+        }
+    }
+}
 
-    cam.render(&world);
+fn usage() -> ! {
+    eprintln!(
+        "usage: pathtracer <scene.toml> [output.{{ppm,png,jpg,exr,hdr,pfm}}] \
+         [--sample-counts <image>]"
+    );
+    process::exit(2);
 }
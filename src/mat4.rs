@@ -0,0 +1,238 @@
+use std::ops::Mul;
+
+use crate::{degrees_to_radians, unit_vector, vec3::cross, Point3, Vec3};
+
+// Affine 4x4 matrix acting on column vectors, stored row by row
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct Mat4 {
+    m: [[f64; 4]; 4],
+}
+
+impl Mat4 {
+    pub fn new(m: [[f64; 4]; 4]) -> Self {
+        Self { m }
+    }
+
+    pub fn identity() -> Self {
+        Self::scale(Vec3::new(1.0, 1.0, 1.0))
+    }
+
+    pub fn translate(offset: Vec3) -> Self {
+        Self::new([
+            [1.0, 0.0, 0.0, offset.x()],
+            [0.0, 1.0, 0.0, offset.y()],
+            [0.0, 0.0, 1.0, offset.z()],
+            [0.0, 0.0, 0.0, 1.0],
+        ])
+    }
+
+    // Non-uniform scale along the axes
+    pub fn scale(factors: Vec3) -> Self {
+        Self::new([
+            [factors.x(), 0.0, 0.0, 0.0],
+            [0.0, factors.y(), 0.0, 0.0],
+            [0.0, 0.0, factors.z(), 0.0],
+            [0.0, 0.0, 0.0, 1.0],
+        ])
+    }
+
+    // Counterclockwise rotation about an axis through the origin, looking down the axis
+    pub fn rotate(axis: Vec3, degrees: f64) -> Self {
+        let a = unit_vector(axis);
+        let (sin, cos) = degrees_to_radians(degrees).sin_cos();
+        let (x, y, z) = (a.x(), a.y(), a.z());
+        let c = 1.0 - cos;
+        Self::new([
+            [
+                x * x * c + cos,
+                x * y * c - z * sin,
+                x * z * c + y * sin,
+                0.0,
+            ],
+            [
+                y * x * c + z * sin,
+                y * y * c + cos,
+                y * z * c - x * sin,
+                0.0,
+            ],
+            [
+                z * x * c - y * sin,
+                z * y * c + x * sin,
+                z * z * c + cos,
+                0.0,
+            ],
+            [0.0, 0.0, 0.0, 1.0],
+        ])
+    }
+
+    // Places an object at from with its +z axis pointing at target and its +y axis as close
+    // to up as possible. This is the object-to-world frame, not a camera view matrix.
+    pub fn look_at(from: Point3, target: Point3, up: Vec3) -> Self {
+        let w = unit_vector(target - from);
+        let u = unit_vector(cross(up, w));
+        let v = cross(w, u);
+        Self::new([
+            [u.x(), v.x(), w.x(), from.x()],
+            [u.y(), v.y(), w.y(), from.y()],
+            [u.z(), v.z(), w.z(), from.z()],
+            [0.0, 0.0, 0.0, 1.0],
+        ])
+    }
+
+    pub fn transpose(&self) -> Self {
+        let mut m = [[0.0; 4]; 4];
+        for (i, row) in m.iter_mut().enumerate() {
+            for (j, value) in row.iter_mut().enumerate() {
+                *value = self.m[j][i];
+            }
+        }
+        Self::new(m)
+    }
+
+    // Determinant of the upper-left 3x3 block, the linear part of the transform
+    pub fn determinant3(&self) -> f64 {
+        let m = &self.m;
+        m[0][0] * (m[1][1] * m[2][2] - m[1][2] * m[2][1])
+            - m[0][1] * (m[1][0] * m[2][2] - m[1][2] * m[2][0])
+            + m[0][2] * (m[1][0] * m[2][1] - m[1][1] * m[2][0])
+    }
+
+    // Inverse of an affine matrix, or None if it collapses space onto a plane or line
+    pub fn inverse(&self) -> Option<Self> {
+        let det = self.determinant3();
+        if det.abs() < 1e-12 {
+            return None;
+        }
+
+        // The linear part inverts through its adjugate, the translation by undoing it
+        let m = &self.m;
+        let cofactor = |r0: usize, r1: usize, c0: usize, c1: usize| {
+            m[r0][c0] * m[r1][c1] - m[r0][c1] * m[r1][c0]
+        };
+        let linear = [
+            [
+                cofactor(1, 2, 1, 2),
+                -cofactor(0, 2, 1, 2),
+                cofactor(0, 1, 1, 2),
+            ],
+            [
+                -cofactor(1, 2, 0, 2),
+                cofactor(0, 2, 0, 2),
+                -cofactor(0, 1, 0, 2),
+            ],
+            [
+                cofactor(1, 2, 0, 1),
+                -cofactor(0, 2, 0, 1),
+                cofactor(0, 1, 0, 1),
+            ],
+        ];
+
+        let mut inv = [[0.0; 4]; 4];
+        for i in 0..3 {
+            for j in 0..3 {
+                inv[i][j] = linear[i][j] / det;
+            }
+            inv[i][3] = -(0..3).map(|k| inv[i][k] * m[k][3]).sum::<f64>();
+        }
+        inv[3][3] = 1.0;
+        Some(Self::new(inv))
+    }
+
+    pub fn transform_point(&self, p: Point3) -> Point3 {
+        self.transform_vector(p) + Vec3::new(self.m[0][3], self.m[1][3], self.m[2][3])
+    }
+
+    // Directions ignore the translation
+    pub fn transform_vector(&self, v: Vec3) -> Vec3 {
+        let row = |i: usize| self.m[i][0] * v.x() + self.m[i][1] * v.y() + self.m[i][2] * v.z();
+        Vec3::new(row(0), row(1), row(2))
+    }
+
+    // Normals map through the inverse transpose; call this on the inverse of the matrix that
+    // moves the points. The result is not normalized.
+    pub fn transform_normal(&self, n: Vec3) -> Vec3 {
+        self.transpose().transform_vector(n)
+    }
+}
+
+impl Default for Mat4 {
+    fn default() -> Self {
+        Self::identity()
+    }
+}
+
+// a * b applies b first, then a
+impl Mul for Mat4 {
+    type Output = Self;
+
+    fn mul(self, other: Self) -> Self {
+        let mut m = [[0.0; 4]; 4];
+        for (i, row) in m.iter_mut().enumerate() {
+            for (j, value) in row.iter_mut().enumerate() {
+                *value = (0..4).map(|k| self.m[i][k] * other.m[k][j]).sum();
+            }
+        }
+        Self::new(m)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(a: Vec3, b: Vec3) {
+        assert!((a - b).near_zero(), "{:?} != {:?}", a, b);
+    }
+
+    #[test]
+    fn test_constructors_move_points() {
+        let p = Point3::new(1.0, 2.0, 3.0);
+        assert_close(
+            Mat4::translate(Vec3::new(1.0, -1.0, 0.5)).transform_point(p),
+            Point3::new(2.0, 1.0, 3.5),
+        );
+        assert_close(
+            Mat4::scale(Vec3::new(2.0, 3.0, -1.0)).transform_point(p),
+            Point3::new(2.0, 6.0, -3.0),
+        );
+        assert_close(
+            Mat4::rotate(Vec3::new(0.0, 0.0, 2.0), 90.0).transform_point(p),
+            Point3::new(-2.0, 1.0, 3.0),
+        );
+
+        let frame = Mat4::look_at(p, Point3::new(1.0, 2.0, 10.0), Vec3::new(0.0, 1.0, 0.0));
+        assert_close(frame.transform_point(Point3::new(0.0, 0.0, 0.0)), p);
+        assert_close(
+            frame.transform_vector(Vec3::new(0.0, 0.0, 1.0)),
+            Vec3::new(0.0, 0.0, 1.0),
+        );
+        assert_close(
+            frame.transform_vector(Vec3::new(0.0, 1.0, 0.0)),
+            Vec3::new(0.0, 1.0, 0.0),
+        );
+    }
+
+    #[test]
+    fn test_inverse_undoes_composition() {
+        let m = Mat4::translate(Vec3::new(1.0, 2.0, 3.0))
+            * Mat4::rotate(Vec3::new(1.0, 1.0, 0.0), 30.0)
+            * Mat4::scale(Vec3::new(2.0, 0.5, 4.0));
+        let inv = m.inverse().unwrap();
+        let p = Point3::new(-0.3, 0.7, 1.1);
+        assert_close(inv.transform_point(m.transform_point(p)), p);
+        assert_close((m * inv).transform_point(p), p);
+        assert!((m.determinant3() - 4.0).abs() < 1e-9);
+
+        assert!(Mat4::scale(Vec3::new(1.0, 0.0, 1.0)).inverse().is_none());
+    }
+
+    #[test]
+    fn test_normals_stay_perpendicular() {
+        let m =
+            Mat4::scale(Vec3::new(3.0, 1.0, 1.0)) * Mat4::rotate(Vec3::new(0.0, 0.0, 1.0), 45.0);
+        let tangent = Vec3::new(1.0, -1.0, 0.0);
+        let normal = Vec3::new(1.0, 1.0, 0.0);
+        let n = m.inverse().unwrap().transform_normal(normal);
+        assert!(crate::dot(n, m.transform_vector(tangent)).abs() < 1e-9);
+    }
+}
@@ -1,145 +1,785 @@
 use crate::{
-    dot, random_double, unit_vector,
-    vec3::{random_unit_vector, reflect, refract},
-    Color, HitRecord, Ray, Vec3,
+    dot,
+    microfacet::{fresnel_conductor, fresnel_dielectric, TrowbridgeReitz},
+    unit_vector,
+    vec3::{random_cosine_direction, random_unit_vector, reflect, refract, Onb},
+    Color, HitRecord, Point3, Ray, Sampler, SolidColor, Texture, Vec3, PI,
 };
+use std::sync::Arc;
 
-pub trait Material {
-    fn scatter(
-        &self,
-        r_in: &Ray,
-        rec: &HitRecord,
-        attenuation: &mut Color,
-        scattered: &mut Ray,
-    ) -> bool;
+// Direction picked by Material::sample
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BsdfSample {
+    pub direction: Vec3,
+    pub weight: Color,  // eval / pdf, or the attenuation of a specular bounce
+    pub pdf: f64,       // Solid angle density of direction; zero for specular bounces
+    pub specular: bool, // Deterministic (or near so) bounce that eval and pdf cannot describe
 }
 
-pub struct DefaultMaterial {
-    albedo: Color,
+// Materials are shared across render threads through Arc, so they must be Send + Sync
+pub trait Material: Send + Sync {
+    // Pick an outgoing direction for light arriving along r_in. None when the path ends here.
+    fn sample(&self, r_in: &Ray, rec: &HitRecord, sampler: &mut dyn Sampler) -> Option<BsdfSample>;
+
+    // BSDF times the cosine with the normal for scattering towards direction. Always black
+    // for specular materials.
+    fn eval(&self, _r_in: &Ray, _rec: &HitRecord, _direction: &Vec3) -> Color {
+        Color::new(0.0, 0.0, 0.0)
+    }
+
+    // Solid angle density with which sample picks direction
+    fn pdf(&self, _r_in: &Ray, _rec: &HitRecord, _direction: &Vec3) -> f64 {
+        0.0
+    }
+
+    // Radiance given off by the surface; most materials emit nothing
+    fn emitted(&self, _u: f64, _v: f64, _p: &Point3) -> Color {
+        Color::new(0.0, 0.0, 0.0)
+    }
 }
 
+pub struct DefaultMaterial {}
+
 impl DefaultMaterial {
     pub fn new() -> Self {
-        DefaultMaterial {
-            albedo: Color::new(0.5, 0.5, 0.5),
-        }
+        DefaultMaterial {}
+    }
+}
+
+impl Default for DefaultMaterial {
+    fn default() -> Self {
+        Self::new()
     }
 }
 
 impl Material for DefaultMaterial {
-    fn scatter(
+    fn sample(
         &self,
         _r_in: &Ray,
         _rec: &HitRecord,
-        _attenuation: &mut Color,
-        _scattered: &mut Ray,
-    ) -> bool {
-        return false;
+        _sampler: &mut dyn Sampler,
+    ) -> Option<BsdfSample> {
+        None
     }
 }
 
 pub struct Lambertian {
-    albedo: Color,
+    tex: Arc<dyn Texture>,
 }
 
 impl Lambertian {
     pub fn new(albedo: Color) -> Self {
-        Lambertian { albedo }
+        Self::from_texture(Arc::new(SolidColor::new(albedo)))
+    }
+
+    pub fn from_texture(tex: Arc<dyn Texture>) -> Self {
+        Lambertian { tex }
     }
 }
 
 impl Material for Lambertian {
-    fn scatter(
-        &self,
-        _r_in: &Ray,
-        rec: &HitRecord,
-        attenuation: &mut Color,
-        scattered: &mut Ray,
-    ) -> bool {
-        let mut scatter_direction = rec.normal + random_unit_vector();
-        if scatter_direction.near_zero() {
-            scatter_direction = rec.normal;
+    // Cosine-weighted directions around the normal
+    fn sample(&self, r_in: &Ray, rec: &HitRecord, sampler: &mut dyn Sampler) -> Option<BsdfSample> {
+        let direction = rec
+            .shading_frame()
+            .transform(random_cosine_direction(sampler));
+        let pdf = self.pdf(r_in, rec, &direction);
+        if pdf <= 0.0 {
+            return None;
         }
-        *scattered = Ray::new(rec.p, scatter_direction);
-        *attenuation = self.albedo;
-        return true;
+        Some(BsdfSample {
+            direction,
+            weight: self.tex.value(rec.u, rec.v, &rec.p),
+            pdf,
+            specular: false,
+        })
+    }
+
+    fn eval(&self, _r_in: &Ray, rec: &HitRecord, direction: &Vec3) -> Color {
+        let cosine = dot(unit_vector(*direction), rec.normal);
+        if cosine <= 0.0 {
+            return Color::new(0.0, 0.0, 0.0);
+        }
+        self.tex.value(rec.u, rec.v, &rec.p) * (cosine / PI)
+    }
+
+    fn pdf(&self, _r_in: &Ray, rec: &HitRecord, direction: &Vec3) -> f64 {
+        let cosine = dot(unit_vector(*direction), rec.normal);
+        f64::max(0.0, cosine / PI)
     }
 }
 
 pub struct Metal {
-    albedo: Color,
+    tex: Arc<dyn Texture>,
     fuzz: f64,
 }
 
 impl Metal {
     pub fn new(albedo: Color, fuzz: f64) -> Self {
+        Self::from_texture(Arc::new(SolidColor::new(albedo)), fuzz)
+    }
+
+    pub fn from_texture(tex: Arc<dyn Texture>, fuzz: f64) -> Self {
         let mod_fuzz = if fuzz < 1.0 { fuzz } else { 1.0 };
         Metal {
-            albedo,
+            tex,
             fuzz: mod_fuzz,
         }
     }
 }
 
 impl Material for Metal {
-    fn scatter(
-        &self,
-        r_in: &Ray,
-        rec: &HitRecord,
-        attenuation: &mut Color,
-        scattered: &mut Ray,
-    ) -> bool {
-        let mut reflected = reflect(r_in.direction(), rec.normal);
-        reflected = unit_vector(reflected) + (self.fuzz * random_unit_vector());
-        *scattered = Ray::new(rec.p, reflected);
-        *attenuation = self.albedo;
-        return dot(scattered.direction(), rec.normal) > 0.0;
+    fn sample(&self, r_in: &Ray, rec: &HitRecord, sampler: &mut dyn Sampler) -> Option<BsdfSample> {
+        let reflected = reflect(r_in.direction(), rec.normal);
+        let direction = unit_vector(reflected) + (self.fuzz * random_unit_vector(sampler));
+        // Fuzzed reflections that end up below the surface are absorbed
+        if dot(direction, rec.normal) <= 0.0 {
+            return None;
+        }
+        Some(BsdfSample {
+            direction,
+            weight: self.tex.value(rec.u, rec.v, &rec.p),
+            pdf: 0.0,
+            specular: true,
+        })
     }
 }
 
+// Measured complex indices of refraction (eta + ik) of some metals, in linear RGB
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConductorPreset {
+    Gold,
+    Copper,
+    Aluminum,
+    Silver,
+}
+
+impl ConductorPreset {
+    // (eta, k) per color channel
+    pub fn ior(self) -> (Color, Color) {
+        match self {
+            ConductorPreset::Gold => (
+                Color::new(0.143, 0.374, 1.442),
+                Color::new(3.983, 2.386, 1.603),
+            ),
+            ConductorPreset::Copper => (
+                Color::new(0.200, 0.924, 1.102),
+                Color::new(3.912, 2.452, 2.142),
+            ),
+            ConductorPreset::Aluminum => (
+                Color::new(1.657, 0.880, 0.521),
+                Color::new(9.224, 6.270, 4.837),
+            ),
+            ConductorPreset::Silver => (
+                Color::new(0.155, 0.117, 0.138),
+                Color::new(4.828, 3.122, 2.147),
+            ),
+        }
+    }
+}
+
+// Rough metal: GGX microfacets with Smith shadowing and the exact conductor Fresnel term.
+// Anisotropic roughness_u runs along the hit's tangent (dp/du) and roughness_v across it. On
+// shapes without a tangent the axes come from the normal alone.
+pub struct Conductor {
+    distribution: TrowbridgeReitz,
+    eta: Color,
+    k: Color,
+}
+
+impl Conductor {
+    pub fn new(eta: Color, k: Color, roughness: f64) -> Self {
+        Self::anisotropic(eta, k, roughness, roughness)
+    }
+
+    pub fn anisotropic(eta: Color, k: Color, roughness_u: f64, roughness_v: f64) -> Self {
+        Conductor {
+            distribution: TrowbridgeReitz::from_roughness(roughness_u, roughness_v),
+            eta,
+            k,
+        }
+    }
+
+    pub fn from_preset(preset: ConductorPreset, roughness: f64) -> Self {
+        let (eta, k) = preset.ior();
+        Self::new(eta, k, roughness)
+    }
+
+    // Outgoing and incoming directions in the shading frame, pointing away from the surface
+    fn local_directions(rec: &HitRecord, r_in: &Ray, direction: &Vec3) -> (Vec3, Vec3) {
+        let frame = rec.shading_frame();
+        let wo = frame.to_local(-unit_vector(r_in.direction()));
+        let wi = frame.to_local(unit_vector(*direction));
+        (wo, wi)
+    }
+}
+
+impl Material for Conductor {
+    // Reflect about a visible microfacet normal
+    fn sample(&self, r_in: &Ray, rec: &HitRecord, sampler: &mut dyn Sampler) -> Option<BsdfSample> {
+        let frame = rec.shading_frame();
+        let wo = frame.to_local(-unit_vector(r_in.direction()));
+        if wo.z() <= 0.0 {
+            return None;
+        }
+
+        let uv = sampler.get_2d();
+        if self.distribution.is_smooth() {
+            return Some(BsdfSample {
+                direction: frame.transform(Vec3::new(-wo.x(), -wo.y(), wo.z())),
+                weight: fresnel_conductor(wo.z(), self.eta, self.k),
+                pdf: 0.0,
+                specular: true,
+            });
+        }
+
+        let wm = self.distribution.sample_wm(wo, uv);
+        let wi = reflect(-wo, wm);
+        if wi.z() <= 0.0 {
+            return None;
+        }
+        let direction = frame.transform(wi);
+        let pdf = self.pdf(r_in, rec, &direction);
+        if pdf <= 0.0 {
+            return None;
+        }
+        Some(BsdfSample {
+            direction,
+            weight: self.eval(r_in, rec, &direction) / pdf,
+            pdf,
+            specular: false,
+        })
+    }
+
+    fn eval(&self, r_in: &Ray, rec: &HitRecord, direction: &Vec3) -> Color {
+        let (wo, wi) = Self::local_directions(rec, r_in, direction);
+        if self.distribution.is_smooth() || wo.z() <= 0.0 || wi.z() <= 0.0 {
+            return Color::new(0.0, 0.0, 0.0);
+        }
+        let wm = unit_vector(wo + wi);
+        let fresnel = fresnel_conductor(dot(wo, wm).abs(), self.eta, self.k);
+        let d_g = self.distribution.d(wm) * self.distribution.g(wo, wi);
+        // f * cos(wi) = D G F / (4 cos(wo))
+        fresnel * (d_g / (4.0 * wo.z()))
+    }
+
+    fn pdf(&self, r_in: &Ray, rec: &HitRecord, direction: &Vec3) -> f64 {
+        let (wo, wi) = Self::local_directions(rec, r_in, direction);
+        if self.distribution.is_smooth() || wo.z() <= 0.0 || wi.z() <= 0.0 {
+            return 0.0;
+        }
+        let wm = unit_vector(wo + wi);
+        // Jacobian of reflecting about wm
+        self.distribution.pdf(wo, wm) / (4.0 * dot(wo, wm).abs())
+    }
+}
+
+// Glass and other clear materials. Smooth by default, with Schlick's reflectance; builder
+// methods add GGX roughness for frosted glass, color absorbed inside, and exact Fresnel.
 pub struct Dielectric {
     refraction_index: f64,
+    distribution: Option<TrowbridgeReitz>, // Microfacet roughness, or None when smooth
+    absorption: Color,                     // Beer-Lambert coefficient per unit distance inside
+    exact_fresnel: bool,
 }
+
 impl Dielectric {
     pub fn new(refraction_index: f64) -> Self {
-        Dielectric { refraction_index }
+        Dielectric {
+            refraction_index,
+            distribution: None,
+            absorption: Color::new(0.0, 0.0, 0.0),
+            exact_fresnel: false,
+        }
+    }
+
+    pub fn with_roughness(mut self, roughness: f64) -> Self {
+        let distribution = TrowbridgeReitz::from_roughness(roughness, roughness);
+        self.distribution = (!distribution.is_smooth()).then_some(distribution);
+        self
+    }
+
+    // Light traveling distance inside keeps the fraction color of its energy. Absorption is
+    // applied where a ray leaves through this material's surface, so it assumes nothing else
+    // sits inside the object.
+    pub fn with_absorption(mut self, color: Color, distance: f64) -> Self {
+        let coefficient = |c: f64| -c.clamp(1e-6, 1.0).ln() / distance;
+        self.absorption = Color::new(
+            coefficient(color.x()),
+            coefficient(color.y()),
+            coefficient(color.z()),
+        );
+        self
     }
-    fn reflectance(&self, cosine: f64, refraction_index: f64) -> f64 {
+
+    pub fn with_exact_fresnel(mut self) -> Self {
+        self.exact_fresnel = true;
+        self
+    }
+
+    // Ratio of the index of refraction beyond the surface to the one the ray arrives in
+    fn eta(&self, rec: &HitRecord) -> f64 {
+        if rec.front_face {
+            self.refraction_index
+        } else {
+            1.0 / self.refraction_index
+        }
+    }
+
+    fn reflectance(&self, cosine: f64, eta: f64) -> f64 {
+        if self.exact_fresnel {
+            return fresnel_dielectric(cosine, eta);
+        }
+        let sin2_t = (1.0 - cosine * cosine) / (eta * eta);
+        if sin2_t > 1.0 {
+            return 1.0;
+        }
         // Use Schlick's approximation for reflectance.
-        let r0 = (1.0 - refraction_index) / (1.0 + refraction_index);
+        let r0 = (1.0 - eta) / (1.0 + eta);
         let r0 = r0 * r0;
-        return r0 + (1.0 - r0) * ((1.0 - cosine).powi(5));
+        r0 + (1.0 - r0) * ((1.0 - cosine).powi(5))
     }
-}
 
-impl Material for Dielectric {
-    fn scatter(
+    // Fraction of the light left after crossing the inside to reach this hit
+    fn transmittance(&self, r_in: &Ray, rec: &HitRecord) -> Color {
+        if rec.front_face || self.absorption.near_zero() {
+            return Color::new(1.0, 1.0, 1.0);
+        }
+        let distance = (rec.p - r_in.origin()).length();
+        let channel = |a: f64| (-a * distance).exp();
+        Color::new(
+            channel(self.absorption.x()),
+            channel(self.absorption.y()),
+            channel(self.absorption.z()),
+        )
+    }
+
+    // Local outgoing and incoming directions with the microfacet normal joining them and the
+    // Fresnel reflectance there, or None for pairs the microfacets cannot connect
+    fn microfacet_geometry(
         &self,
         r_in: &Ray,
         rec: &HitRecord,
-        attenuation: &mut Color,
-        scattered: &mut Ray,
-    ) -> bool {
-        *attenuation = Color::new(1.0, 1.0, 1.0);
-        let ri: f64 = if rec.front_face {
-            1.0 / self.refraction_index
+        direction: &Vec3,
+    ) -> Option<(Vec3, Vec3, Vec3, f64)> {
+        let frame = rec.shading_frame();
+        let wo = frame.to_local(-unit_vector(r_in.direction()));
+        let wi = frame.to_local(unit_vector(*direction));
+        if wo.z() <= 0.0 || wi.z() == 0.0 {
+            return None;
+        }
+
+        // Generalized half vector, which also covers refraction
+        let eta = self.eta(rec);
+        let scaled_wi = if wi.z() > 0.0 { wi } else { eta * wi };
+        let wm = scaled_wi + wo;
+        if wm.near_zero() {
+            return None;
+        }
+        let wm = unit_vector(wm);
+        let wm = if wm.z() < 0.0 { -wm } else { wm };
+        if dot(wm, wi) * wi.z() < 0.0 || dot(wm, wo) < 0.0 {
+            return None;
+        }
+
+        Some((wo, wi, wm, self.reflectance(dot(wo, wm), eta)))
+    }
+}
+
+// Refract wo about the microfacet normal wm on its side, or None on total internal reflection
+fn refract_microfacet(wo: Vec3, wm: Vec3, eta: f64) -> Option<Vec3> {
+    let cos_i = dot(wo, wm);
+    let sin2_t = (1.0 - cos_i * cos_i).max(0.0) / (eta * eta);
+    if sin2_t >= 1.0 {
+        return None;
+    }
+    let cos_t = (1.0 - sin2_t).sqrt();
+    Some(-wo / eta + (cos_i / eta - cos_t) * wm)
+}
+
+impl Material for Dielectric {
+    fn sample(&self, r_in: &Ray, rec: &HitRecord, sampler: &mut dyn Sampler) -> Option<BsdfSample> {
+        let Some(distribution) = self.distribution else {
+            let eta = self.eta(rec);
+            let cos_theta = f64::min(dot(-unit_vector(r_in.direction()), rec.normal), 1.0);
+            let reflectance = self.reflectance(cos_theta, eta);
+
+            let unit_direction = unit_vector(r_in.direction());
+
+            let direction: Vec3 = if reflectance >= 1.0 || reflectance > sampler.get_1d() {
+                reflect(unit_direction, rec.normal)
+            } else {
+                refract(unit_direction, rec.normal, 1.0 / eta)
+            };
+
+            return Some(BsdfSample {
+                direction,
+                weight: self.transmittance(r_in, rec),
+                pdf: 0.0,
+                specular: true,
+            });
+        };
+
+        // Pick a visible microfacet, then reflect or refract through it in proportion to the
+        // Fresnel reflectance
+        let frame = rec.shading_frame();
+        let wo = frame.to_local(-unit_vector(r_in.direction()));
+        if wo.z() <= 0.0 {
+            return None;
+        }
+        let wm = distribution.sample_wm(wo, sampler.get_2d());
+        let eta = self.eta(rec);
+        let reflectance = self.reflectance(dot(wo, wm), eta);
+        let wi = if sampler.get_1d() < reflectance {
+            reflect(-wo, wm)
         } else {
-            self.refraction_index
+            refract_microfacet(wo, wm, eta)?
         };
-        let cos_theta = f64::min(dot(-unit_vector(r_in.direction()), rec.normal), 1.0);
-        let sin_theta = (1.0 - cos_theta * cos_theta).sqrt();
-        let cannot_refract: bool = ri * sin_theta > 1.0;
 
-        let direction: Vec3;
-        let unit_direction = unit_vector(r_in.direction());
+        let direction = frame.transform(wi);
+        let pdf = self.pdf(r_in, rec, &direction);
+        if pdf <= 0.0 {
+            return None;
+        }
+        Some(BsdfSample {
+            direction,
+            weight: self.eval(r_in, rec, &direction) / pdf,
+            pdf,
+            specular: false,
+        })
+    }
+
+    // Radiance is not scaled by eta^2 across the boundary, matching the smooth case; paths
+    // that enter and leave the glass get the same result either way
+    fn eval(&self, r_in: &Ray, rec: &HitRecord, direction: &Vec3) -> Color {
+        let Some(distribution) = self.distribution else {
+            return Color::new(0.0, 0.0, 0.0);
+        };
+        let Some((wo, wi, wm, reflectance)) = self.microfacet_geometry(r_in, rec, direction) else {
+            return Color::new(0.0, 0.0, 0.0);
+        };
 
-        if cannot_refract || self.reflectance(cos_theta, ri) > random_double() {
-            direction = reflect(unit_direction, rec.normal);
+        let d_g = distribution.d(wm) * distribution.g(wo, wi);
+        let value = if wi.z() > 0.0 {
+            reflectance * d_g / (4.0 * wo.z())
         } else {
-            direction = refract(unit_direction, rec.normal, ri);
+            let eta = self.eta(rec);
+            let denom = (dot(wi, wm) + dot(wo, wm) / eta).powi(2);
+            (1.0 - reflectance) * d_g * (dot(wi, wm) * dot(wo, wm)).abs() / (wo.z() * denom)
+        };
+        self.transmittance(r_in, rec) * value
+    }
+
+    fn pdf(&self, r_in: &Ray, rec: &HitRecord, direction: &Vec3) -> f64 {
+        let Some(distribution) = self.distribution else {
+            return 0.0;
+        };
+        let Some((wo, wi, wm, reflectance)) = self.microfacet_geometry(r_in, rec, direction) else {
+            return 0.0;
+        };
+
+        // Density of the microfacet normal, times the Jacobian from normals to directions
+        let pdf_wm = distribution.pdf(wo, wm);
+        if wi.z() > 0.0 {
+            reflectance * pdf_wm / (4.0 * dot(wo, wm).abs())
+        } else {
+            let eta = self.eta(rec);
+            let denom = (dot(wi, wm) + dot(wo, wm) / eta).powi(2);
+            (1.0 - reflectance) * pdf_wm * dot(wi, wm).abs() / denom
         }
+    }
+}
+
+pub struct DiffuseLight {
+    tex: Arc<dyn Texture>,
+}
+
+impl DiffuseLight {
+    pub fn new(emit: Color) -> Self {
+        Self::from_texture(Arc::new(SolidColor::new(emit)))
+    }
+
+    pub fn from_texture(tex: Arc<dyn Texture>) -> Self {
+        DiffuseLight { tex }
+    }
+}
+
+impl Material for DiffuseLight {
+    fn sample(
+        &self,
+        _r_in: &Ray,
+        _rec: &HitRecord,
+        _sampler: &mut dyn Sampler,
+    ) -> Option<BsdfSample> {
+        None
+    }
+
+    fn emitted(&self, u: f64, v: f64, p: &Point3) -> Color {
+        self.tex.value(u, v, p)
+    }
+}
+
+// Phase function of a participating medium: scatters equally in every direction
+pub struct Isotropic {
+    tex: Arc<dyn Texture>,
+}
+
+impl Isotropic {
+    pub fn new(albedo: Color) -> Self {
+        Self::from_texture(Arc::new(SolidColor::new(albedo)))
+    }
+
+    pub fn from_texture(tex: Arc<dyn Texture>) -> Self {
+        Isotropic { tex }
+    }
+}
+
+impl Material for Isotropic {
+    fn sample(&self, r_in: &Ray, rec: &HitRecord, sampler: &mut dyn Sampler) -> Option<BsdfSample> {
+        let direction = random_unit_vector(sampler);
+        Some(BsdfSample {
+            direction,
+            weight: self.tex.value(rec.u, rec.v, &rec.p),
+            pdf: self.pdf(r_in, rec, &direction),
+            specular: false,
+        })
+    }
+
+    // There is no surface, so no cosine term
+    fn eval(&self, r_in: &Ray, rec: &HitRecord, direction: &Vec3) -> Color {
+        self.tex.value(rec.u, rec.v, &rec.p) * self.pdf(r_in, rec, direction)
+    }
+
+    fn pdf(&self, _r_in: &Ray, _rec: &HitRecord, _direction: &Vec3) -> f64 {
+        1.0 / (4.0 * PI)
+    }
+}
 
-        *scattered = Ray::new(rec.p, direction);
-        return true;
+// Phase function of media that scatter mostly forwards (g > 0) or backwards (g < 0)
+pub struct HenyeyGreenstein {
+    tex: Arc<dyn Texture>,
+    g: f64, // Mean cosine of the scattering angle, in (-1, 1)
+}
+
+impl HenyeyGreenstein {
+    pub fn new(albedo: Color, g: f64) -> Self {
+        Self::from_texture(Arc::new(SolidColor::new(albedo)), g)
+    }
+
+    pub fn from_texture(tex: Arc<dyn Texture>, g: f64) -> Self {
+        HenyeyGreenstein {
+            tex,
+            g: g.clamp(-0.99, 0.99),
+        }
+    }
+
+    // Density over directions at angle theta from the direction of travel
+    fn phase(&self, cos_theta: f64) -> f64 {
+        let g = self.g;
+        let denom = 1.0 + g * g - 2.0 * g * cos_theta;
+        (1.0 - g * g) / (4.0 * PI * denom * denom.sqrt())
+    }
+}
+
+impl Material for HenyeyGreenstein {
+    fn sample(&self, r_in: &Ray, rec: &HitRecord, sampler: &mut dyn Sampler) -> Option<BsdfSample> {
+        // Invert the cumulative distribution of cos theta
+        let (a, b) = sampler.get_2d();
+        let g = self.g;
+        let cos_theta = if g.abs() < 1e-3 {
+            1.0 - 2.0 * a
+        } else {
+            let sq = (1.0 - g * g) / (1.0 + g - 2.0 * g * a);
+            ((1.0 + g * g - sq * sq) / (2.0 * g)).clamp(-1.0, 1.0)
+        };
+        let sin_theta = (1.0 - cos_theta * cos_theta).max(0.0).sqrt();
+        let phi = 2.0 * PI * b;
+        let local = Vec3::new(sin_theta * phi.cos(), sin_theta * phi.sin(), cos_theta);
+        let direction = Onb::new(unit_vector(r_in.direction())).transform(local);
+
+        Some(BsdfSample {
+            direction,
+            weight: self.tex.value(rec.u, rec.v, &rec.p),
+            pdf: self.phase(cos_theta),
+            specular: false,
+        })
+    }
+
+    fn eval(&self, r_in: &Ray, rec: &HitRecord, direction: &Vec3) -> Color {
+        self.tex.value(rec.u, rec.v, &rec.p) * self.pdf(r_in, rec, direction)
+    }
+
+    fn pdf(&self, r_in: &Ray, _rec: &HitRecord, direction: &Vec3) -> f64 {
+        self.phase(dot(unit_vector(r_in.direction()), unit_vector(*direction)))
+    }
+}
+
+// Hit at the origin on a tilted front face, for material tests
+#[cfg(test)]
+pub(crate) fn test_hit_record() -> HitRecord {
+    let mut rec = HitRecord::new();
+    rec.p = Point3::new(0.0, 0.0, 0.0);
+    rec.normal = unit_vector(Vec3::new(0.2, 1.0, -0.1));
+    rec.front_face = true;
+    rec
+}
+
+// Sample mat once, checking a non-specular result against its pdf and eval. None when the
+// path ends.
+#[cfg(test)]
+pub(crate) fn sample_consistently(
+    mat: &dyn Material,
+    r_in: &Ray,
+    rec: &HitRecord,
+    sampler: &mut dyn Sampler,
+) -> Option<BsdfSample> {
+    let sample = mat.sample(r_in, rec, sampler)?;
+    if !sample.specular {
+        let pdf = mat.pdf(r_in, rec, &sample.direction);
+        assert!((sample.pdf - pdf).abs() < 1e-9 * pdf);
+        let weight = mat.eval(r_in, rec, &sample.direction) / pdf;
+        assert!((weight - sample.weight).near_zero());
+    }
+    Some(sample)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::IndependentSampler;
+
+    #[test]
+    fn test_lambertian_sample_matches_eval_and_pdf() {
+        let albedo = Color::new(0.8, 0.5, 0.2);
+        let mat = Lambertian::new(albedo);
+        let rec = test_hit_record();
+        let r_in = Ray::new(Point3::new(0.0, 1.0, 1.0), Vec3::new(0.0, -1.0, -1.0));
+        let mut sampler = IndependentSampler::new(1);
+
+        let mut mean_cosine = 0.0;
+        let count = 20000;
+        for _ in 0..count {
+            let sample = sample_consistently(&mat, &r_in, &rec, &mut sampler).unwrap();
+            assert!(!sample.specular);
+            mean_cosine += dot(unit_vector(sample.direction), rec.normal);
+        }
+        // Cosine-weighted directions have a mean cosine of 2/3
+        assert!((mean_cosine / count as f64 - 2.0 / 3.0).abs() < 0.01);
+
+        let below = -rec.normal;
+        assert_eq!(mat.pdf(&r_in, &rec, &below), 0.0);
+        assert_eq!(mat.eval(&r_in, &rec, &below), Color::new(0.0, 0.0, 0.0));
+    }
+
+    #[test]
+    fn test_henyey_greenstein_mean_cosine_is_g() {
+        let rec = test_hit_record();
+        let r_in = Ray::new(Point3::new(0.0, 1.0, 1.0), Vec3::new(0.3, -1.0, -1.0));
+        let mut sampler = IndependentSampler::new(2);
+        let count = 20000;
+
+        for g in [-0.6, 0.0, 0.8] {
+            let mat = HenyeyGreenstein::new(Color::new(0.5, 0.5, 0.5), g);
+            let mut mean_cosine = 0.0;
+            for _ in 0..count {
+                let sample = sample_consistently(&mat, &r_in, &rec, &mut sampler).unwrap();
+                mean_cosine += dot(unit_vector(r_in.direction()), sample.direction);
+            }
+            assert!((mean_cosine / count as f64 - g).abs() < 0.02);
+        }
+    }
+
+    #[test]
+    fn test_conductor_sample_matches_eval_and_conserves_energy() {
+        let rec = test_hit_record();
+        let r_in = Ray::new(Point3::new(0.0, 1.0, 1.0), Vec3::new(0.3, -1.0, -0.8));
+        let mut sampler = IndependentSampler::new(3);
+        let count = 20000;
+
+        // A perfect reflector (F = 1) loses only the energy of light shadowed between facets
+        let mirror_like = Color::new(1e4, 1e4, 1e4);
+        for (roughness_u, roughness_v) in [(0.3, 0.3), (0.1, 0.4)] {
+            let mat = Conductor::anisotropic(mirror_like, mirror_like, roughness_u, roughness_v);
+            let mut albedo = 0.0;
+            for _ in 0..count {
+                let Some(sample) = sample_consistently(&mat, &r_in, &rec, &mut sampler) else {
+                    continue;
+                };
+                assert!(!sample.specular);
+                albedo += sample.weight.x();
+            }
+            let albedo = albedo / count as f64;
+            assert!(albedo <= 1.0 && albedo > 0.9);
+        }
+
+        let gold = Conductor::from_preset(ConductorPreset::Gold, 0.0);
+        let sample = gold.sample(&r_in, &rec, &mut sampler).unwrap();
+        assert!(sample.specular);
+        assert!(sample.weight.x() > sample.weight.z());
+    }
+
+    #[test]
+    fn test_rough_dielectric_sample_matches_eval_and_conserves_energy() {
+        let r_in = Ray::new(Point3::new(0.0, 1.0, 1.0), Vec3::new(0.3, -1.0, -0.8));
+        let mut sampler = IndependentSampler::new(4);
+        let mat = Dielectric::new(1.5)
+            .with_roughness(0.3)
+            .with_exact_fresnel();
+        let count = 20000;
+
+        // From outside and from inside the glass
+        for front_face in [true, false] {
+            let mut rec = test_hit_record();
+            rec.front_face = front_face;
+            let (mut reflected, mut transmitted) = (0.0, 0.0);
+            for _ in 0..count {
+                let Some(sample) = sample_consistently(&mat, &r_in, &rec, &mut sampler) else {
+                    continue;
+                };
+                assert!(!sample.specular);
+                if dot(sample.direction, rec.normal) > 0.0 {
+                    reflected += sample.weight.x();
+                } else {
+                    transmitted += sample.weight.x();
+                }
+            }
+            let (reflected, transmitted) = (reflected / count as f64, transmitted / count as f64);
+            assert!(reflected + transmitted <= 1.0 && reflected + transmitted > 0.9);
+            assert!(reflected > 0.0 && transmitted > 0.0);
+        }
+    }
+
+    #[test]
+    fn test_dielectric_absorbs_along_the_inside() {
+        let mat = Dielectric::new(1.5).with_absorption(Color::new(0.5, 1.0, 0.25), 2.0);
+        let mut sampler = IndependentSampler::new(1);
+
+        // Leaving the glass after 4 units inside, or entering it from outside
+        let r_in = Ray::new(Point3::new(0.0, -4.0, 0.0), Vec3::new(0.0, 1.0, 0.0));
+        let mut rec = test_hit_record();
+        rec.normal = Vec3::new(0.0, -1.0, 0.0);
+        rec.front_face = false;
+        let sample = mat.sample(&r_in, &rec, &mut sampler).unwrap();
+        assert!((sample.weight - Color::new(0.25, 1.0, 0.0625)).near_zero());
+
+        rec.front_face = true;
+        let sample = mat.sample(&r_in, &rec, &mut sampler).unwrap();
+        assert_eq!(sample.weight, Color::new(1.0, 1.0, 1.0));
+    }
+
+    #[test]
+    fn test_specular_materials_have_no_density() {
+        let rec = test_hit_record();
+        let r_in = Ray::new(Point3::new(0.0, 1.0, 1.0), Vec3::new(0.0, -1.0, -1.0));
+        let mut sampler = IndependentSampler::new(1);
+        let materials: [Arc<dyn Material>; 2] = [
+            Arc::new(Metal::new(Color::new(0.9, 0.9, 0.9), 0.0)),
+            Arc::new(Dielectric::new(1.5)),
+        ];
+        for mat in materials {
+            let sample = mat.sample(&r_in, &rec, &mut sampler).unwrap();
+            assert!(sample.specular);
+            assert_eq!(mat.pdf(&r_in, &rec, &sample.direction), 0.0);
+        }
     }
 }
@@ -0,0 +1,222 @@
+use crate::{
+    dot,
+    triangle::{intersect_triangle, triangle_bounding_box},
+    unit_vector,
+    vec3::cross,
+    Aabb, BvhNode, HitRecord, Hittable, HittableList, Interval, Material, Point3, Ray,
+    SplitStrategy, Vec3,
+};
+use std::sync::Arc;
+
+// Vertex buffers shared by every triangle of a mesh
+struct MeshData {
+    positions: Vec<Point3>,
+    normals: Vec<Vec3>,       // Per-vertex shading normals, empty when absent
+    uvs: Vec<[f64; 2]>,       // Per-vertex texture coordinates, empty when absent
+    indices: Vec<[usize; 3]>, // Vertex indices of each triangle
+    mat: Arc<dyn Material>,
+}
+
+// An indexed triangle mesh. The triangles only hold an index into the shared buffers and
+// are kept in a BVH built when the mesh is created.
+pub struct TriangleMesh {
+    bvh: BvhNode,
+    triangle_count: usize,
+}
+
+impl TriangleMesh {
+    // normals and uvs are either empty or hold one entry per position
+    pub fn new(
+        positions: Vec<Point3>,
+        normals: Vec<Vec3>,
+        uvs: Vec<[f64; 2]>,
+        indices: Vec<[usize; 3]>,
+        mat: Arc<dyn Material>,
+    ) -> Self {
+        assert!(
+            normals.is_empty() || normals.len() == positions.len(),
+            "Mesh normals must match the vertex count"
+        );
+        assert!(
+            uvs.is_empty() || uvs.len() == positions.len(),
+            "Mesh UVs must match the vertex count"
+        );
+        assert!(
+            indices.iter().flatten().all(|&i| i < positions.len()),
+            "Mesh index out of range"
+        );
+
+        let triangle_count = indices.len();
+        let data = Arc::new(MeshData {
+            positions,
+            normals: normals.into_iter().map(unit_vector).collect(),
+            uvs,
+            indices,
+            mat,
+        });
+
+        let mut triangles = HittableList::new();
+        for index in 0..triangle_count {
+            triangles.add(Arc::new(MeshTriangle::new(data.clone(), index)));
+        }
+
+        Self {
+            bvh: BvhNode::new(triangles, SplitStrategy::Sah),
+            triangle_count,
+        }
+    }
+
+    pub fn triangle_count(&self) -> usize {
+        self.triangle_count
+    }
+}
+
+impl Hittable for TriangleMesh {
+    fn hit_tmin_tmax(&self, r: &Ray, ray_tmin: f64, ray_tmax: f64, rec: &mut HitRecord) -> bool {
+        self.bvh.hit_tmin_tmax(r, ray_tmin, ray_tmax, rec)
+    }
+
+    fn hit_interval(&self, r: &Ray, ray_t: Interval, rec: &mut HitRecord) -> bool {
+        self.bvh.hit_interval(r, ray_t, rec)
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.bvh.bounding_box()
+    }
+}
+
+struct MeshTriangle {
+    mesh: Arc<MeshData>,
+    index: usize,
+    bbox: Aabb,
+}
+
+impl MeshTriangle {
+    fn new(mesh: Arc<MeshData>, index: usize) -> Self {
+        let [i0, i1, i2] = mesh.indices[index];
+        let bbox =
+            triangle_bounding_box(mesh.positions[i0], mesh.positions[i1], mesh.positions[i2]);
+        Self { mesh, index, bbox }
+    }
+}
+
+impl Hittable for MeshTriangle {
+    fn hit_tmin_tmax(&self, r: &Ray, ray_tmin: f64, ray_tmax: f64, rec: &mut HitRecord) -> bool {
+        self.hit_interval(r, Interval::new(ray_tmin, ray_tmax), rec)
+    }
+
+    fn hit_interval(&self, r: &Ray, ray_t: Interval, rec: &mut HitRecord) -> bool {
+        let mesh = &self.mesh;
+        let [i0, i1, i2] = mesh.indices[self.index];
+        let (v0, v1, v2) = (mesh.positions[i0], mesh.positions[i1], mesh.positions[i2]);
+
+        let Some((t, b1, b2)) = intersect_triangle(r, ray_t, v0, v1, v2) else {
+            return false;
+        };
+        let b0 = 1.0 - b1 - b2;
+
+        rec.t = t;
+        rec.p = r.at(t);
+        let outward_normal = unit_vector(cross(v1 - v0, v2 - v0));
+        rec.set_face_normal(r, &outward_normal);
+
+        if !mesh.normals.is_empty() {
+            // Interpolate the shading normal, keeping it on the same side as the hit face
+            let shading_normal =
+                unit_vector(b0 * mesh.normals[i0] + b1 * mesh.normals[i1] + b2 * mesh.normals[i2]);
+            rec.normal = if dot(shading_normal, rec.normal) < 0.0 {
+                -shading_normal
+            } else {
+                shading_normal
+            };
+        }
+
+        let (e1, e2) = (v1 - v0, v2 - v0);
+        rec.tangent = e1;
+        if mesh.uvs.is_empty() {
+            rec.u = b1;
+            rec.v = b2;
+        } else {
+            let (uv0, uv1, uv2) = (mesh.uvs[i0], mesh.uvs[i1], mesh.uvs[i2]);
+            rec.u = b0 * uv0[0] + b1 * uv1[0] + b2 * uv2[0];
+            rec.v = b0 * uv0[1] + b1 * uv1[1] + b2 * uv2[1];
+
+            // Solve the edges for dp/du, keeping the first edge when the uvs are degenerate
+            let (du1, dv1) = (uv1[0] - uv0[0], uv1[1] - uv0[1]);
+            let (du2, dv2) = (uv2[0] - uv0[0], uv2[1] - uv0[1]);
+            let det = du1 * dv2 - du2 * dv1;
+            if det.abs() > 1e-12 {
+                rec.tangent = (dv2 * e1 - dv1 * e2) / det;
+            }
+        }
+
+        rec.barycentric = Vec3::new(b0, b1, b2);
+        rec.mat = mesh.mat.clone();
+
+        true
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.bbox
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::DefaultMaterial;
+
+    fn quad_mesh(normals: Vec<Vec3>) -> TriangleMesh {
+        let positions = vec![
+            Point3::new(-1.0, -1.0, 0.0),
+            Point3::new(1.0, -1.0, 0.0),
+            Point3::new(1.0, 1.0, 0.0),
+            Point3::new(-1.0, 1.0, 0.0),
+        ];
+        let uvs = vec![[0.0, 0.0], [1.0, 0.0], [1.0, 1.0], [0.0, 1.0]];
+        TriangleMesh::new(
+            positions,
+            normals,
+            uvs,
+            vec![[0, 1, 2], [0, 2, 3]],
+            Arc::new(DefaultMaterial::new()),
+        )
+    }
+
+    #[test]
+    fn test_hit_interpolates_uv() {
+        let mesh = quad_mesh(Vec::new());
+        let r = Ray::new(Point3::new(0.5, -0.5, 2.0), Vec3::new(0.0, 0.0, -1.0));
+        let mut rec = HitRecord::new();
+
+        assert!(mesh.hit_interval(&r, Interval::new(0.001, f64::INFINITY), &mut rec));
+        assert!((rec.t - 2.0).abs() < 1e-9);
+        assert!((rec.u - 0.75).abs() < 1e-9);
+        assert!((rec.v - 0.25).abs() < 1e-9);
+        assert!(rec.front_face);
+        assert_eq!(rec.normal, Vec3::new(0.0, 0.0, 1.0));
+        let b = rec.barycentric;
+        assert!((b.x() + b.y() + b.z() - 1.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_hit_interpolates_shading_normal() {
+        let tilted = unit_vector(Vec3::new(1.0, 0.0, 1.0));
+        let mesh = quad_mesh(vec![tilted; 4]);
+        let r = Ray::new(Point3::new(0.0, 0.0, -2.0), Vec3::new(0.0, 0.0, 1.0));
+        let mut rec = HitRecord::new();
+
+        assert!(mesh.hit_interval(&r, Interval::new(0.001, f64::INFINITY), &mut rec));
+        assert!(!rec.front_face);
+        assert!((rec.normal - (-tilted)).near_zero());
+    }
+
+    #[test]
+    fn test_miss_outside_mesh() {
+        let mesh = quad_mesh(Vec::new());
+        let r = Ray::new(Point3::new(1.5, 0.0, 2.0), Vec3::new(0.0, 0.0, -1.0));
+        let mut rec = HitRecord::new();
+        assert!(!mesh.hit_interval(&r, Interval::new(0.001, f64::INFINITY), &mut rec));
+        assert_eq!(mesh.triangle_count(), 2);
+    }
+}
@@ -0,0 +1,215 @@
+use crate::{dot, unit_vector, vec3::cross, Color, Vec3, PI};
+
+// Trowbridge-Reitz (GGX) distribution of microfacet normals. Directions are in the local
+// shading frame, with the macro normal along +z and the roughness axes along x and y.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TrowbridgeReitz {
+    alpha_x: f64,
+    alpha_y: f64,
+}
+
+impl TrowbridgeReitz {
+    pub fn new(alpha_x: f64, alpha_y: f64) -> Self {
+        Self {
+            alpha_x: alpha_x.max(1e-4),
+            alpha_y: alpha_y.max(1e-4),
+        }
+    }
+
+    // Perceptual roughness in [0, 1] maps to alpha = roughness^2
+    pub fn from_roughness(roughness_x: f64, roughness_y: f64) -> Self {
+        let alpha = |r: f64| r.clamp(0.0, 1.0).powi(2);
+        Self::new(alpha(roughness_x), alpha(roughness_y))
+    }
+
+    // So close to a mirror that it is better treated as one
+    pub fn is_smooth(&self) -> bool {
+        self.alpha_x.max(self.alpha_y) < 1e-3
+    }
+
+    // Density of microfacet normals wm, per unit projected area
+    pub fn d(&self, wm: Vec3) -> f64 {
+        if wm.z() <= 0.0 {
+            return 0.0;
+        }
+        let e = (wm.x() / self.alpha_x).powi(2) + (wm.y() / self.alpha_y).powi(2) + wm.z().powi(2);
+        1.0 / (PI * self.alpha_x * self.alpha_y * e * e)
+    }
+
+    // Smith's auxiliary function for the microfacets hidden from w
+    pub fn lambda(&self, w: Vec3) -> f64 {
+        let cos2 = w.z() * w.z();
+        if cos2 == 0.0 {
+            return f64::INFINITY;
+        }
+        let alpha2_tan2 = ((self.alpha_x * w.x()).powi(2) + (self.alpha_y * w.y()).powi(2)) / cos2;
+        ((1.0 + alpha2_tan2).sqrt() - 1.0) / 2.0
+    }
+
+    // Fraction of microfacets visible from w
+    pub fn g1(&self, w: Vec3) -> f64 {
+        1.0 / (1.0 + self.lambda(w))
+    }
+
+    // Fraction visible from both wo and wi (height-correlated Smith)
+    pub fn g(&self, wo: Vec3, wi: Vec3) -> f64 {
+        1.0 / (1.0 + self.lambda(wo) + self.lambda(wi))
+    }
+
+    // Density of the normals sample_wm picks: the normals visible from w
+    pub fn pdf(&self, w: Vec3, wm: Vec3) -> f64 {
+        let cos = w.z().abs();
+        if cos == 0.0 {
+            return 0.0;
+        }
+        self.g1(w) / cos * self.d(wm) * dot(w, wm).max(0.0)
+    }
+
+    // Sample a normal among those visible from w (Heitz 2018): stretch w to the hemisphere
+    // configuration, sample the projected half disk, and unstretch
+    pub fn sample_wm(&self, w: Vec3, (a, b): (f64, f64)) -> Vec3 {
+        let w = if w.z() < 0.0 { -w } else { w };
+        let wh = unit_vector(Vec3::new(self.alpha_x * w.x(), self.alpha_y * w.y(), w.z()));
+
+        let len2 = wh.x() * wh.x() + wh.y() * wh.y();
+        let t1 = if len2 > 0.0 {
+            Vec3::new(-wh.y(), wh.x(), 0.0) / len2.sqrt()
+        } else {
+            Vec3::new(1.0, 0.0, 0.0)
+        };
+        let t2 = cross(wh, t1);
+
+        let r = a.sqrt();
+        let phi = 2.0 * PI * b;
+        let p1 = r * phi.cos();
+        let s = 0.5 * (1.0 + wh.z());
+        let p2 = (1.0 - s) * (1.0 - p1 * p1).max(0.0).sqrt() + s * r * phi.sin();
+        let pz = (1.0 - p1 * p1 - p2 * p2).max(0.0).sqrt();
+        let nh = p1 * t1 + p2 * t2 + pz * wh;
+
+        unit_vector(Vec3::new(
+            self.alpha_x * nh.x(),
+            self.alpha_y * nh.y(),
+            nh.z().max(1e-6),
+        ))
+    }
+}
+
+// Unpolarized Fresnel reflectance of a conductor with complex index of refraction eta + ik,
+// per color channel, for light arriving at cos_theta from the normal
+pub fn fresnel_conductor(cos_theta: f64, eta: Color, k: Color) -> Color {
+    let cos2 = cos_theta.clamp(0.0, 1.0).powi(2);
+    let sin2 = 1.0 - cos2;
+    let channel = |eta: f64, k: f64| {
+        let t0 = eta * eta - k * k - sin2;
+        let a2_plus_b2 = (t0 * t0 + 4.0 * eta * eta * k * k).sqrt();
+        let a = (0.5 * (a2_plus_b2 + t0)).max(0.0).sqrt();
+        let t1 = a2_plus_b2 + cos2;
+        let t2 = 2.0 * cos_theta.clamp(0.0, 1.0) * a;
+        let rs = (t1 - t2) / (t1 + t2);
+        let t3 = cos2 * a2_plus_b2 + sin2 * sin2;
+        let t4 = t2 * sin2;
+        let rp = rs * (t3 - t4) / (t3 + t4);
+        0.5 * (rs + rp)
+    };
+    Color::new(
+        channel(eta.x(), k.x()),
+        channel(eta.y(), k.y()),
+        channel(eta.z(), k.z()),
+    )
+}
+
+// Unpolarized Fresnel reflectance of a dielectric boundary, where eta is the index of
+// refraction on the far side over that on the near side. Total internal reflection gives 1.
+pub fn fresnel_dielectric(cos_theta_i: f64, eta: f64) -> f64 {
+    let cos_i = cos_theta_i.clamp(0.0, 1.0);
+    let sin2_t = (1.0 - cos_i * cos_i) / (eta * eta);
+    if sin2_t >= 1.0 {
+        return 1.0;
+    }
+    let cos_t = (1.0 - sin2_t).sqrt();
+    let r_parallel = (eta * cos_i - cos_t) / (eta * cos_i + cos_t);
+    let r_perpendicular = (cos_i - eta * cos_t) / (cos_i + eta * cos_t);
+    0.5 * (r_parallel * r_parallel + r_perpendicular * r_perpendicular)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{vec3::random_unit_vector, IndependentSampler, Sampler};
+
+    #[test]
+    fn test_projected_normals_cover_unit_area() {
+        // The microfacets project onto exactly the macro surface: integral of D(m) cos(m) = 1
+        let mut sampler = IndependentSampler::new(4);
+        for distribution in [
+            TrowbridgeReitz::new(0.3, 0.3),
+            TrowbridgeReitz::new(0.1, 0.6),
+        ] {
+            let count = 200000;
+            let mut total = 0.0;
+            for _ in 0..count {
+                let m = random_unit_vector(&mut sampler);
+                total += distribution.d(m) * m.z().max(0.0) * 4.0 * PI;
+            }
+            assert!((total / count as f64 - 1.0).abs() < 0.05);
+        }
+    }
+
+    #[test]
+    fn test_visible_normal_samples_match_pdf() {
+        let distribution = TrowbridgeReitz::new(0.5, 0.2);
+        let w = unit_vector(Vec3::new(0.4, -0.3, 0.6));
+        let mut sampler = IndependentSampler::new(5);
+
+        // Moments of the sampled normals, against the same moments integrated from the pdf
+        // over the sphere: its total, the mean normal and the share of normals near the pole
+        let moments = |m: Vec3| [1.0, m.x(), m.y(), m.z(), f64::from(m.z() > 0.95)];
+        let count = 400000;
+        let mut sampled = [0.0; 5];
+        let mut integrated = [0.0; 5];
+        for _ in 0..count {
+            let m = distribution.sample_wm(w, sampler.get_2d());
+            // Visible normals face w
+            assert!(dot(m, w) >= 0.0 && m.z() > 0.0);
+            for (total, moment) in sampled.iter_mut().zip(moments(m)) {
+                *total += moment / count as f64;
+            }
+
+            let m = random_unit_vector(&mut sampler);
+            let density = distribution.pdf(w, m) * 4.0 * PI;
+            for (total, moment) in integrated.iter_mut().zip(moments(m)) {
+                *total += density * moment / count as f64;
+            }
+        }
+        for (sampled, integrated) in sampled.iter().zip(integrated) {
+            assert!(
+                (sampled - integrated).abs() < 0.01,
+                "{sampled} vs {integrated}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_fresnel_conductor_limits() {
+        let eta = Color::new(0.2, 0.9, 1.1);
+        let k = Color::new(3.9, 2.5, 2.1);
+        let normal = fresnel_conductor(1.0, eta, k);
+        let f0 = |n: f64, k: f64| ((n - 1.0).powi(2) + k * k) / ((n + 1.0).powi(2) + k * k);
+        assert!((normal.x() - f0(0.2, 3.9)).abs() < 1e-9);
+        assert!((normal.z() - f0(1.1, 2.1)).abs() < 1e-9);
+
+        let grazing = fresnel_conductor(0.0, eta, k);
+        assert!((grazing - Color::new(1.0, 1.0, 1.0)).near_zero());
+    }
+
+    #[test]
+    fn test_fresnel_dielectric_limits() {
+        assert!((fresnel_dielectric(1.0, 1.5) - 0.04).abs() < 1e-12);
+        assert!((fresnel_dielectric(1.0, 1.0 / 1.5) - 0.04).abs() < 1e-12);
+        assert_eq!(fresnel_dielectric(0.0, 1.5), 1.0);
+        // Past the critical angle of about 41.8 degrees inside glass
+        assert_eq!(fresnel_dielectric(0.7, 1.0 / 1.5), 1.0);
+        assert!(fresnel_dielectric(0.8, 1.0 / 1.5) < 1.0);
+    }
+}
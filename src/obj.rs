@@ -0,0 +1,499 @@
+use crate::{
+    principled::scalar, Color, HittableList, Material, Point3, Principled, TriangleMesh, Vec3,
+};
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+#[derive(Debug)]
+pub enum ObjError {
+    Io {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    Parse {
+        path: PathBuf,
+        line: usize,
+        message: String,
+    },
+}
+
+impl fmt::Display for ObjError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ObjError::Io { path, source } => write!(f, "{}: {}", path.display(), source),
+            ObjError::Parse {
+                path,
+                line,
+                message,
+            } => write!(f, "{}:{}: {}", path.display(), line, message),
+        }
+    }
+}
+
+impl std::error::Error for ObjError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ObjError::Io { source, .. } => Some(source),
+            ObjError::Parse { .. } => None,
+        }
+    }
+}
+
+// Load a Wavefront .obj file together with the .mtl libraries it references.
+// Every group/material combination becomes one TriangleMesh in the returned list.
+pub fn load_obj(path: impl AsRef<Path>) -> Result<HittableList, ObjError> {
+    let path = path.as_ref();
+    let source = read_to_string(path)?;
+    parse_obj(&source, path)
+}
+
+// Load the materials of a .mtl file, keyed by name
+pub fn load_mtl(path: impl AsRef<Path>) -> Result<HashMap<String, Arc<dyn Material>>, ObjError> {
+    let path = path.as_ref();
+    let source = read_to_string(path)?;
+    parse_mtl(&source, path)
+}
+
+fn read_to_string(path: &Path) -> Result<String, ObjError> {
+    fs::read_to_string(path).map_err(|source| ObjError::Io {
+        path: path.to_path_buf(),
+        source,
+    })
+}
+
+// A face corner: position, texture coordinate and normal indices (zero based)
+type Corner = (usize, Option<usize>, Option<usize>);
+
+// Faces sharing one group and material, collected into a single mesh
+struct MeshBuilder {
+    group: String,
+    material: Option<String>,
+    faces: Vec<[Corner; 3]>,
+}
+
+fn parse_obj(source: &str, path: &Path) -> Result<HittableList, ObjError> {
+    let base_dir = path.parent().unwrap_or_else(|| Path::new(""));
+    let error = |line: usize, message: String| ObjError::Parse {
+        path: path.to_path_buf(),
+        line,
+        message,
+    };
+
+    let mut positions: Vec<Point3> = Vec::new();
+    let mut uvs: Vec<[f64; 2]> = Vec::new();
+    let mut normals: Vec<Vec3> = Vec::new();
+    let mut materials: HashMap<String, Arc<dyn Material>> = HashMap::new();
+
+    let mut builders: Vec<MeshBuilder> = Vec::new();
+    let mut group = String::from("default");
+    let mut material: Option<String> = None;
+
+    for (index, raw_line) in source.lines().enumerate() {
+        let line_number = index + 1;
+        let line = strip_comment(raw_line);
+        let mut tokens = line.split_whitespace();
+        let Some(keyword) = tokens.next() else {
+            continue;
+        };
+        let args: Vec<&str> = tokens.collect();
+
+        match keyword {
+            "v" => {
+                let v = parse_floats(&args, 3, 4).map_err(|m| error(line_number, m))?;
+                positions.push(Point3::new(v[0], v[1], v[2]));
+            }
+            "vt" => {
+                let vt = parse_floats(&args, 1, 3).map_err(|m| error(line_number, m))?;
+                uvs.push([vt[0], vt.get(1).copied().unwrap_or(0.0)]);
+            }
+            "vn" => {
+                let n = parse_floats(&args, 3, 3).map_err(|m| error(line_number, m))?;
+                normals.push(Vec3::new(n[0], n[1], n[2]));
+            }
+            "f" => {
+                if args.len() < 3 {
+                    return Err(error(line_number, "face needs at least 3 vertices".into()));
+                }
+                let corners = args
+                    .iter()
+                    .map(|arg| {
+                        parse_corner(arg, positions.len(), uvs.len(), normals.len())
+                            .map_err(|m| error(line_number, m))
+                    })
+                    .collect::<Result<Vec<Corner>, ObjError>>()?;
+
+                let builder = current_builder(&mut builders, &group, &material);
+                // Triangulate the polygon as a fan around its first vertex
+                for i in 1..corners.len() - 1 {
+                    builder.faces.push([corners[0], corners[i], corners[i + 1]]);
+                }
+            }
+            "g" | "o" => {
+                group = if args.is_empty() {
+                    String::from("default")
+                } else {
+                    args.join(" ")
+                };
+            }
+            "usemtl" => {
+                let name = args.join(" ");
+                if !materials.contains_key(&name) {
+                    return Err(error(line_number, format!("unknown material '{}'", name)));
+                }
+                material = Some(name);
+            }
+            "mtllib" => {
+                if args.is_empty() {
+                    return Err(error(line_number, "mtllib needs a file name".into()));
+                }
+                for file in &args {
+                    materials.extend(load_mtl(base_dir.join(file))?);
+                }
+            }
+            // Smoothing groups, lines, points and free-form geometry are not supported
+            _ => {}
+        }
+    }
+
+    let default_material: Arc<dyn Material> = Arc::new(Principled::new(Color::new(0.5, 0.5, 0.5)));
+    let mut list = HittableList::new();
+    for builder in builders {
+        let mat = match &builder.material {
+            Some(name) => materials[name].clone(),
+            None => default_material.clone(),
+        };
+        let mesh = build_mesh(&builder, &positions, &uvs, &normals, mat);
+        list.add(Arc::new(mesh));
+    }
+    Ok(list)
+}
+
+fn current_builder<'a>(
+    builders: &'a mut Vec<MeshBuilder>,
+    group: &str,
+    material: &Option<String>,
+) -> &'a mut MeshBuilder {
+    let position = builders
+        .iter()
+        .position(|b| b.group == group && b.material == *material);
+    match position {
+        Some(i) => &mut builders[i],
+        None => {
+            builders.push(MeshBuilder {
+                group: group.to_string(),
+                material: material.clone(),
+                faces: Vec::new(),
+            });
+            builders.last_mut().unwrap()
+        }
+    }
+}
+
+// Flatten the separately indexed OBJ attributes into one vertex buffer per mesh
+fn build_mesh(
+    builder: &MeshBuilder,
+    positions: &[Point3],
+    uvs: &[[f64; 2]],
+    normals: &[Vec3],
+    mat: Arc<dyn Material>,
+) -> TriangleMesh {
+    let corners = || builder.faces.iter().flatten();
+    // Shading normals are only used when every corner of the mesh has one
+    let use_normals = corners().all(|c| c.2.is_some());
+    let use_uvs = corners().any(|c| c.1.is_some());
+
+    let mut remap: HashMap<Corner, usize> = HashMap::new();
+    let mut mesh_positions = Vec::new();
+    let mut mesh_normals = Vec::new();
+    let mut mesh_uvs = Vec::new();
+    let mut indices = Vec::with_capacity(builder.faces.len());
+
+    for face in &builder.faces {
+        let mut triangle = [0usize; 3];
+        for (k, corner) in face.iter().enumerate() {
+            triangle[k] = *remap.entry(*corner).or_insert_with(|| {
+                mesh_positions.push(positions[corner.0]);
+                if use_normals {
+                    mesh_normals.push(normals[corner.2.unwrap()]);
+                }
+                if use_uvs {
+                    mesh_uvs.push(corner.1.map(|i| uvs[i]).unwrap_or([0.0, 0.0]));
+                }
+                mesh_positions.len() - 1
+            });
+        }
+        indices.push(triangle);
+    }
+
+    TriangleMesh::new(mesh_positions, mesh_normals, mesh_uvs, indices, mat)
+}
+
+fn parse_mtl(source: &str, path: &Path) -> Result<HashMap<String, Arc<dyn Material>>, ObjError> {
+    let error = |line: usize, message: String| ObjError::Parse {
+        path: path.to_path_buf(),
+        line,
+        message,
+    };
+
+    let mut materials = HashMap::new();
+    let mut current: Option<(String, MtlProperties)> = None;
+
+    for (index, raw_line) in source.lines().enumerate() {
+        let line_number = index + 1;
+        let line = strip_comment(raw_line);
+        let mut tokens = line.split_whitespace();
+        let Some(keyword) = tokens.next() else {
+            continue;
+        };
+        let args: Vec<&str> = tokens.collect();
+
+        if keyword == "newmtl" {
+            if args.is_empty() {
+                return Err(error(line_number, "newmtl needs a name".into()));
+            }
+            if let Some((name, props)) = current.take() {
+                materials.insert(name, props.to_material());
+            }
+            current = Some((args.join(" "), MtlProperties::default()));
+            continue;
+        }
+
+        let Some((_, props)) = current.as_mut() else {
+            return Err(error(
+                line_number,
+                format!("'{}' before any newmtl", keyword),
+            ));
+        };
+        let parse = |min, max| parse_floats(&args, min, max).map_err(|m| error(line_number, m));
+
+        match keyword {
+            "Kd" => props.kd = parse_color(&parse(1, 3)?),
+            "Ks" => props.ks = parse_color(&parse(1, 3)?),
+            "Ns" => props.ns = parse(1, 1)?[0],
+            "Ni" => props.ni = parse(1, 1)?[0],
+            "d" => props.dissolve = parse(1, 1)?[0],
+            "Tr" => props.dissolve = 1.0 - parse(1, 1)?[0],
+            "illum" => props.illum = parse(1, 1)?[0] as u32,
+            // Texture maps and other statements are not supported yet
+            _ => {}
+        }
+    }
+
+    if let Some((name, props)) = current {
+        materials.insert(name, props.to_material());
+    }
+    Ok(materials)
+}
+
+struct MtlProperties {
+    kd: Color,
+    ks: Color,
+    ns: f64,
+    ni: f64,
+    dissolve: f64,
+    illum: u32,
+}
+
+impl Default for MtlProperties {
+    fn default() -> Self {
+        Self {
+            kd: Color::new(0.8, 0.8, 0.8),
+            ks: Color::new(0.0, 0.0, 0.0),
+            ns: 0.0,
+            ni: 1.5,
+            dissolve: 1.0,
+            illum: 2,
+        }
+    }
+}
+
+impl MtlProperties {
+    // Map the Phong-style MTL parameters onto a principled material
+    fn to_material(&self) -> Arc<dyn Material> {
+        let max_component = |c: Color| f64::max(c.x(), f64::max(c.y(), c.z()));
+        // Sharper specular exponents give smoother surfaces
+        let roughness = (2.0 / (self.ns + 2.0)).sqrt();
+
+        let glass = matches!(self.illum, 4 | 6 | 7 | 9);
+        if glass || self.dissolve < 1.0 {
+            // Glass illumination models are clear; a partial dissolve keeps the diffuse color
+            let (base_color, transmission) = if glass {
+                (Color::new(1.0, 1.0, 1.0), 1.0)
+            } else {
+                (self.kd, 1.0 - self.dissolve.max(0.0))
+            };
+            let roughness = if glass { 0.0 } else { roughness };
+            return Arc::new(
+                Principled::new(base_color)
+                    .with_roughness(scalar(roughness))
+                    .with_transmission(scalar(transmission), self.ni),
+            );
+        }
+
+        let reflective = self.illum == 3
+            || (max_component(self.ks) > 0.0 && max_component(self.ks) >= max_component(self.kd));
+        if reflective {
+            return Arc::new(
+                Principled::new(self.ks)
+                    .with_metallic(scalar(1.0))
+                    .with_roughness(scalar(roughness)),
+            );
+        }
+
+        // Without Ks there is no highlight beyond the grazing Fresnel reflection
+        let specular = if max_component(self.ks) > 0.0 {
+            0.5
+        } else {
+            0.0
+        };
+        Arc::new(
+            Principled::new(self.kd)
+                .with_roughness(scalar(roughness))
+                .with_specular(scalar(specular)),
+        )
+    }
+}
+
+fn strip_comment(line: &str) -> &str {
+    match line.find('#') {
+        Some(i) => &line[..i],
+        None => line,
+    }
+}
+
+fn parse_floats(args: &[&str], min: usize, max: usize) -> Result<Vec<f64>, String> {
+    if args.len() < min || args.len() > max {
+        return Err(if min == max {
+            format!("expected {} values, found {}", min, args.len())
+        } else {
+            format!("expected {} to {} values, found {}", min, max, args.len())
+        });
+    }
+    args.iter()
+        .map(|arg| {
+            arg.parse::<f64>()
+                .map_err(|_| format!("invalid number '{}'", arg))
+        })
+        .collect()
+}
+
+// A single value means a grey color
+fn parse_color(values: &[f64]) -> Color {
+    match values {
+        [v] => Color::new(*v, *v, *v),
+        [r, g, b, ..] => Color::new(*r, *g, *b),
+        _ => Color::new(0.0, 0.0, 0.0),
+    }
+}
+
+// Parse a v, v/vt, v//vn or v/vt/vn face corner, resolving negative (relative) indices
+fn parse_corner(
+    arg: &str,
+    position_count: usize,
+    uv_count: usize,
+    normal_count: usize,
+) -> Result<Corner, String> {
+    let mut parts = arg.split('/');
+    let position = resolve_index(parts.next().unwrap_or(""), position_count, "vertex")?;
+    let uv = match parts.next() {
+        Some("") | None => None,
+        Some(s) => Some(resolve_index(s, uv_count, "texture coordinate")?),
+    };
+    let normal = match parts.next() {
+        Some("") | None => None,
+        Some(s) => Some(resolve_index(s, normal_count, "normal")?),
+    };
+    if parts.next().is_some() {
+        return Err(format!("invalid face vertex '{}'", arg));
+    }
+    Ok((position, uv, normal))
+}
+
+fn resolve_index(s: &str, count: usize, what: &str) -> Result<usize, String> {
+    let index: i64 = s
+        .parse()
+        .map_err(|_| format!("invalid {} index '{}'", what, s))?;
+    let resolved = if index > 0 {
+        index - 1
+    } else {
+        count as i64 + index
+    };
+    if index == 0 || resolved < 0 || resolved >= count as i64 {
+        return Err(format!("{} index {} out of range", what, index));
+    }
+    Ok(resolved as usize)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{HitRecord, Hittable, Interval, Ray};
+
+    const QUAD: &str = "\
+# A unit quad split into two groups
+v -1 -1 0
+v 1 -1 0
+v 1 1 0
+v -1 1 0
+vt 0 0
+vt 1 0
+vt 1 1
+vt 0 1
+vn 0 0 1
+g front
+f 1/1/1 2/2/1 3/3/1 4/4/1
+g back
+f -4 -2 -3
+";
+
+    #[test]
+    fn test_parse_groups_and_ngons() {
+        let list = parse_obj(QUAD, Path::new("quad.obj")).unwrap();
+        assert_eq!(list.objects().len(), 2);
+
+        let r = Ray::new(Point3::new(0.5, -0.5, 1.0), Vec3::new(0.0, 0.0, -1.0));
+        let mut rec = HitRecord::new();
+        assert!(list.hit_interval(&r, Interval::new(0.001, f64::INFINITY), &mut rec));
+        assert!((rec.t - 1.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_parse_error_reports_line() {
+        let source = "v 0 0 0\nv 1 0 0\nv 0 1 0\nf 1 2 7\n";
+        match parse_obj(source, Path::new("bad.obj")) {
+            Err(ObjError::Parse { line, .. }) => assert_eq!(line, 4),
+            _ => panic!("expected a parse error"),
+        }
+    }
+
+    #[test]
+    fn test_unknown_material_is_an_error() {
+        let source = "v 0 0 0\nusemtl missing\n";
+        let err = parse_obj(source, Path::new("bad.obj")).err().unwrap();
+        assert_eq!(err.to_string(), "bad.obj:2: unknown material 'missing'");
+    }
+
+    #[test]
+    fn test_parse_mtl() {
+        let source = "\
+newmtl matte
+Kd 0.8 0.1 0.1
+newmtl glass
+Ni 1.45
+d 0.2
+newmtl bad
+Ns shiny
+";
+        let err = parse_mtl(source, Path::new("scene.mtl")).err().unwrap();
+        assert_eq!(err.to_string(), "scene.mtl:7: invalid number 'shiny'");
+
+        let materials = parse_mtl(
+            &source[..source.find("newmtl bad").unwrap()],
+            Path::new("x"),
+        );
+        assert_eq!(materials.unwrap().len(), 2);
+    }
+}
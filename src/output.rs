@@ -0,0 +1,301 @@
+use crate::{color_to_bytes, write_color, Framebuffer, ToneMapper};
+use image::codecs::hdr::HdrEncoder;
+use image::ImageFormat;
+use std::fmt;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutputFormat {
+    Ppm,     // Plain-text 8-bit PPM
+    Png,     // 8-bit PNG
+    Jpeg,    // 8-bit JPEG
+    OpenExr, // 32-bit float OpenEXR, linear
+    Hdr,     // Radiance RGBE, linear
+    Pfm,     // Portable FloatMap, linear
+}
+
+impl OutputFormat {
+    // Pick the format matching the file extension
+    pub fn from_path(path: impl AsRef<Path>) -> Option<Self> {
+        let extension = path.as_ref().extension()?.to_str()?.to_ascii_lowercase();
+        match extension.as_str() {
+            "ppm" => Some(OutputFormat::Ppm),
+            "png" => Some(OutputFormat::Png),
+            "jpg" | "jpeg" => Some(OutputFormat::Jpeg),
+            "exr" => Some(OutputFormat::OpenExr),
+            "hdr" => Some(OutputFormat::Hdr),
+            "pfm" => Some(OutputFormat::Pfm),
+            _ => None,
+        }
+    }
+
+    // Float formats store the linear radiance without clamping or quantizing
+    pub fn is_hdr(&self) -> bool {
+        matches!(
+            self,
+            OutputFormat::OpenExr | OutputFormat::Hdr | OutputFormat::Pfm
+        )
+    }
+}
+
+#[derive(Debug)]
+pub enum OutputError {
+    Io(io::Error),
+    Image(image::ImageError),
+}
+
+impl fmt::Display for OutputError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            OutputError::Io(err) => write!(f, "{}", err),
+            OutputError::Image(err) => write!(f, "{}", err),
+        }
+    }
+}
+
+impl std::error::Error for OutputError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            OutputError::Io(err) => Some(err),
+            OutputError::Image(err) => Some(err),
+        }
+    }
+}
+
+impl From<io::Error> for OutputError {
+    fn from(err: io::Error) -> Self {
+        OutputError::Io(err)
+    }
+}
+
+impl From<image::ImageError> for OutputError {
+    fn from(err: image::ImageError) -> Self {
+        OutputError::Image(err)
+    }
+}
+
+// Write the framebuffer as a plain-text (P3) PPM image
+pub fn write_ppm<W: Write>(
+    framebuffer: &Framebuffer,
+    tone_mapper: &ToneMapper,
+    out: &mut W,
+) -> io::Result<()> {
+    writeln!(
+        out,
+        "P3\n{} {}\n255",
+        framebuffer.width(),
+        framebuffer.height()
+    )?;
+    for pixel_color in framebuffer.pixels() {
+        write_color(out, &tone_mapper.map(*pixel_color))?;
+    }
+    Ok(())
+}
+
+pub fn save_ppm(
+    framebuffer: &Framebuffer,
+    tone_mapper: &ToneMapper,
+    path: impl AsRef<Path>,
+) -> Result<(), OutputError> {
+    let mut out = BufWriter::new(File::create(path)?);
+    write_ppm(framebuffer, tone_mapper, &mut out)?;
+    out.flush()?;
+    Ok(())
+}
+
+// Tone map and convert to an sRGB-encoded 8-bit image
+pub fn to_rgb8(framebuffer: &Framebuffer, tone_mapper: &ToneMapper) -> image::RgbImage {
+    let mut img = image::RgbImage::new(framebuffer.width(), framebuffer.height());
+    for (pixel, color) in img.pixels_mut().zip(framebuffer.pixels()) {
+        *pixel = image::Rgb(color_to_bytes(&tone_mapper.map(*color)));
+    }
+    img
+}
+
+// Save as an 8-bit image in the format given by the file extension (png, jpg, bmp, ...)
+pub fn save_image(
+    framebuffer: &Framebuffer,
+    tone_mapper: &ToneMapper,
+    path: impl AsRef<Path>,
+) -> Result<(), OutputError> {
+    to_rgb8(framebuffer, tone_mapper).save(path)?;
+    Ok(())
+}
+
+// Convert to a linear 32-bit float image
+pub fn to_rgb32f(framebuffer: &Framebuffer) -> image::Rgb32FImage {
+    let mut img = image::Rgb32FImage::new(framebuffer.width(), framebuffer.height());
+    for (pixel, color) in img.pixels_mut().zip(framebuffer.pixels()) {
+        *pixel = image::Rgb([color.x() as f32, color.y() as f32, color.z() as f32]);
+    }
+    img
+}
+
+// Write the framebuffer as a little-endian Portable FloatMap. PFM stores rows bottom to top.
+pub fn write_pfm<W: Write>(framebuffer: &Framebuffer, out: &mut W) -> io::Result<()> {
+    write!(
+        out,
+        "PF\n{} {}\n-1.0\n",
+        framebuffer.width(),
+        framebuffer.height()
+    )?;
+    for y in (0..framebuffer.height()).rev() {
+        for x in 0..framebuffer.width() {
+            let color = framebuffer.pixel(x, y);
+            for c in [color.x(), color.y(), color.z()] {
+                out.write_all(&(c as f32).to_le_bytes())?;
+            }
+        }
+    }
+    Ok(())
+}
+
+// Write the framebuffer as a Radiance RGBE (.hdr) image
+pub fn write_hdr<W: Write>(framebuffer: &Framebuffer, out: &mut W) -> Result<(), OutputError> {
+    let img = to_rgb32f(framebuffer);
+    let pixels: Vec<image::Rgb<f32>> = img.pixels().copied().collect();
+    HdrEncoder::new(out).encode(
+        &pixels,
+        framebuffer.width() as usize,
+        framebuffer.height() as usize,
+    )?;
+    Ok(())
+}
+
+// Save the framebuffer in the requested format, whatever the file extension.
+// The tone mapper only applies to 8-bit formats; float formats keep the linear radiance.
+pub fn save(
+    framebuffer: &Framebuffer,
+    path: impl AsRef<Path>,
+    format: OutputFormat,
+    tone_mapper: &ToneMapper,
+) -> Result<(), OutputError> {
+    let path = path.as_ref();
+    match format {
+        OutputFormat::Ppm => save_ppm(framebuffer, tone_mapper, path),
+        OutputFormat::Png => {
+            let img = to_rgb8(framebuffer, tone_mapper);
+            Ok(img.save_with_format(path, ImageFormat::Png)?)
+        }
+        OutputFormat::Jpeg => {
+            let img = to_rgb8(framebuffer, tone_mapper);
+            Ok(img.save_with_format(path, ImageFormat::Jpeg)?)
+        }
+        OutputFormat::OpenExr => {
+            Ok(to_rgb32f(framebuffer).save_with_format(path, ImageFormat::OpenExr)?)
+        }
+        OutputFormat::Hdr => {
+            let mut out = BufWriter::new(File::create(path)?);
+            write_hdr(framebuffer, &mut out)?;
+            out.flush()?;
+            Ok(())
+        }
+        OutputFormat::Pfm => {
+            let mut out = BufWriter::new(File::create(path)?);
+            write_pfm(framebuffer, &mut out)?;
+            out.flush()?;
+            Ok(())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Color;
+
+    #[test]
+    fn test_write_ppm() {
+        let mut framebuffer = Framebuffer::new(2, 1);
+        framebuffer.set_pixel(1, 0, Color::new(1.0, 0.25, 0.0));
+
+        let mut out = Vec::new();
+        write_ppm(&framebuffer, &ToneMapper::default(), &mut out).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "P3\n2 1\n255\n0 0 0\n255 137 0\n"
+        );
+    }
+
+    #[test]
+    fn test_write_pfm_keeps_unclamped_values() {
+        let mut framebuffer = Framebuffer::new(1, 2);
+        framebuffer.set_pixel(0, 0, Color::new(12.5, 0.0, 0.0));
+
+        let mut out = Vec::new();
+        write_pfm(&framebuffer, &mut out).unwrap();
+        let header = b"PF\n1 2\n-1.0\n";
+        assert_eq!(&out[..header.len()], header);
+        assert_eq!(out.len(), header.len() + 2 * 3 * 4);
+
+        // The top row is written last
+        let red = &out[out.len() - 12..out.len() - 8];
+        assert_eq!(f32::from_le_bytes(red.try_into().unwrap()), 12.5);
+    }
+
+    #[test]
+    fn test_format_from_path() {
+        assert_eq!(
+            OutputFormat::from_path("a.EXR"),
+            Some(OutputFormat::OpenExr)
+        );
+        assert_eq!(OutputFormat::from_path("a.jpeg"), Some(OutputFormat::Jpeg));
+        assert_eq!(OutputFormat::from_path("a.tiff"), None);
+        assert!(OutputFormat::Pfm.is_hdr());
+        assert!(!OutputFormat::Png.is_hdr());
+    }
+
+    // Save two bright pixels in a float format and return the file's path
+    fn save_bright_pixels(format: OutputFormat, name: &str) -> std::path::PathBuf {
+        let mut framebuffer = Framebuffer::new(2, 1);
+        framebuffer.set_pixel(0, 0, Color::new(12.5, 3.0, 0.25));
+        framebuffer.set_pixel(1, 0, Color::new(0.5, 1.0, 40.0));
+
+        let dir = std::env::temp_dir().join("pathtracer_output_test");
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join(name);
+        save(&framebuffer, &path, format, &ToneMapper::default()).unwrap();
+        path
+    }
+
+    #[test]
+    fn test_exr_round_trip_keeps_unclamped_values() {
+        let path = save_bright_pixels(OutputFormat::OpenExr, "out.exr");
+        let img = image::open(path).unwrap().into_rgb32f();
+        assert_eq!(img.dimensions(), (2, 1));
+        assert_eq!(img.get_pixel(0, 0).0, [12.5, 3.0, 0.25]);
+        assert_eq!(img.get_pixel(1, 0).0, [0.5, 1.0, 40.0]);
+    }
+
+    #[test]
+    fn test_hdr_round_trip_keeps_unclamped_values() {
+        let path = save_bright_pixels(OutputFormat::Hdr, "out.hdr");
+        // image::open would tone map RGBE down to 8 bits, so read the floats directly
+        let reader = std::io::BufReader::new(File::open(path).unwrap());
+        let decoder = image::codecs::hdr::HdrDecoder::new(reader).unwrap();
+        let pixels = decoder.read_image_hdr().unwrap();
+        assert_eq!(pixels.len(), 2);
+
+        // RGBE shares one exponent per pixel, leaving each channel about 8 bits relative to
+        // the brightest one
+        let close = |a: image::Rgb<f32>, b: [f32; 3], max: f32| {
+            a.0.iter().zip(b).all(|(x, y)| (x - y).abs() <= max / 128.0)
+        };
+        assert!(close(pixels[0], [12.5, 3.0, 0.25], 12.5));
+        assert!(close(pixels[1], [0.5, 1.0, 40.0], 40.0));
+        assert!(pixels[1].0[2] > 39.0);
+    }
+
+    #[test]
+    fn test_save_image_reports_errors() {
+        let framebuffer = Framebuffer::new(1, 1);
+        let result = save_image(
+            &framebuffer,
+            &ToneMapper::default(),
+            "missing-directory/out.png",
+        );
+        assert!(result.is_err());
+    }
+}
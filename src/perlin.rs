@@ -0,0 +1,154 @@
+use crate::{dot, unit_vector, IndependentSampler, Point3, Sampler, Vec3};
+
+const POINT_COUNT: usize = 256;
+// Seed used by Perlin::new, so noise textures look the same from one render to the next
+const DEFAULT_SEED: u64 = 0x5EED;
+
+// Ken Perlin's gradient noise with random unit vectors at the lattice points
+pub struct Perlin {
+    randvec: [Vec3; POINT_COUNT],
+    perm_x: [usize; POINT_COUNT],
+    perm_y: [usize; POINT_COUNT],
+    perm_z: [usize; POINT_COUNT],
+}
+
+impl Perlin {
+    pub fn new() -> Self {
+        Self::with_seed(DEFAULT_SEED)
+    }
+
+    pub fn with_seed(seed: u64) -> Self {
+        let mut sampler = IndependentSampler::new(seed);
+        let mut randvec = [Vec3::default(); POINT_COUNT];
+        for v in randvec.iter_mut() {
+            *v = unit_vector(Vec3::random_range(-1.0, 1.0, &mut sampler));
+        }
+
+        Self {
+            randvec,
+            perm_x: Self::generate_perm(&mut sampler),
+            perm_y: Self::generate_perm(&mut sampler),
+            perm_z: Self::generate_perm(&mut sampler),
+        }
+    }
+
+    pub fn noise(&self, p: &Point3) -> f64 {
+        let u = p.x() - p.x().floor();
+        let v = p.y() - p.y().floor();
+        let w = p.z() - p.z().floor();
+
+        let i = p.x().floor() as i64;
+        let j = p.y().floor() as i64;
+        let k = p.z().floor() as i64;
+        let mut c = [[[Vec3::default(); 2]; 2]; 2];
+
+        for (di, plane) in c.iter_mut().enumerate() {
+            for (dj, row) in plane.iter_mut().enumerate() {
+                for (dk, corner) in row.iter_mut().enumerate() {
+                    *corner = self.randvec[self.perm_x[((i + di as i64) & 255) as usize]
+                        ^ self.perm_y[((j + dj as i64) & 255) as usize]
+                        ^ self.perm_z[((k + dk as i64) & 255) as usize]];
+                }
+            }
+        }
+
+        Self::perlin_interp(&c, u, v, w)
+    }
+
+    // Sum of several octaves of noise at decreasing weights
+    pub fn turb(&self, p: &Point3, depth: u32) -> f64 {
+        let mut accum = 0.0;
+        let mut temp_p = *p;
+        let mut weight = 1.0;
+
+        for _ in 0..depth {
+            accum += weight * self.noise(&temp_p);
+            weight *= 0.5;
+            temp_p *= 2.0;
+        }
+
+        accum.abs()
+    }
+
+    fn generate_perm(sampler: &mut dyn Sampler) -> [usize; POINT_COUNT] {
+        let mut p = [0usize; POINT_COUNT];
+        for (i, value) in p.iter_mut().enumerate() {
+            *value = i;
+        }
+
+        // Fisher-Yates shuffle
+        for i in (1..POINT_COUNT).rev() {
+            let target = ((sampler.random_double() * (i + 1) as f64) as usize).min(i);
+            p.swap(i, target);
+        }
+        p
+    }
+
+    fn perlin_interp(c: &[[[Vec3; 2]; 2]; 2], u: f64, v: f64, w: f64) -> f64 {
+        // Hermite smoothing of the interpolation weights
+        let uu = u * u * (3.0 - 2.0 * u);
+        let vv = v * v * (3.0 - 2.0 * v);
+        let ww = w * w * (3.0 - 2.0 * w);
+        let mut accum = 0.0;
+
+        for (i, plane) in c.iter().enumerate() {
+            for (j, row) in plane.iter().enumerate() {
+                for (k, corner) in row.iter().enumerate() {
+                    let (fi, fj, fk) = (i as f64, j as f64, k as f64);
+                    let weight_v = Vec3::new(u - fi, v - fj, w - fk);
+                    accum += (fi * uu + (1.0 - fi) * (1.0 - uu))
+                        * (fj * vv + (1.0 - fj) * (1.0 - vv))
+                        * (fk * ww + (1.0 - fk) * (1.0 - ww))
+                        * dot(*corner, weight_v);
+                }
+            }
+        }
+
+        accum
+    }
+}
+
+impl Default for Perlin {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_noise_vanishes_on_the_lattice() {
+        let perlin = Perlin::new();
+        for p in [
+            Point3::new(0.0, 0.0, 0.0),
+            Point3::new(3.0, -2.0, 7.0),
+            Point3::new(-300.0, 12.0, 256.0),
+        ] {
+            assert_eq!(perlin.noise(&p), 0.0);
+        }
+    }
+
+    #[test]
+    fn test_noise_is_seeded_and_bounded() {
+        let a = Perlin::with_seed(7);
+        let b = Perlin::with_seed(7);
+        let other = Perlin::with_seed(8);
+        let mut differs = false;
+        for k in 0..1000 {
+            let t = k as f64 * 0.0731;
+            let p = Point3::new(t, 2.0 * t.sin(), t.cos() - 0.3 * t);
+            let n = a.noise(&p);
+            assert_eq!(n, b.noise(&p));
+            assert!((-1.0..=1.0).contains(&n));
+            assert!(a.turb(&p, 7) >= 0.0);
+            differs |= n != other.noise(&p);
+        }
+        assert!(differs);
+        assert_eq!(
+            Perlin::new().noise(&Point3::new(0.3, 0.4, 0.5)),
+            Perlin::default().noise(&Point3::new(0.3, 0.4, 0.5))
+        );
+    }
+}
@@ -0,0 +1,131 @@
+use crate::{
+    dot, unit_vector, vec3::Onb, Aabb, HitRecord, Hittable, Interval, Material, Point3, Ray, Vec3,
+};
+use std::sync::Arc;
+
+// Half-width of the bounding box given to a plane. The plane itself is unbounded, but the BVH
+// needs finite boxes to compare split costs.
+const PLANE_EXTENT: f64 = 1e6;
+
+// Infinite plane through a point. Texture coordinates are the planar coordinates along two
+// in-plane axes, wrapped to [0, 1) so image textures repeat every unit.
+pub struct Plane {
+    point: Point3,
+    normal: Vec3,
+    frame: Onb, // w is the normal; u and v span the plane
+    mat: Arc<dyn Material>,
+    bbox: Aabb,
+}
+
+impl Plane {
+    pub fn new(point: Point3, normal: Vec3, mat: Arc<dyn Material>) -> Self {
+        let normal = unit_vector(normal);
+        let frame = Onb::new(normal);
+
+        // Planes facing along an axis get a flat box around them on that axis; every other
+        // extent is centered on point so that planes far from the origin stay inside it
+        let axis = |i: usize| {
+            let aligned = (0..3).all(|k| k == i || normal[k] == 0.0);
+            if aligned {
+                Interval::new(point[i], point[i])
+            } else {
+                Interval::new(point[i] - PLANE_EXTENT, point[i] + PLANE_EXTENT)
+            }
+        };
+
+        Self {
+            point,
+            normal,
+            frame,
+            mat,
+            bbox: Aabb::new(axis(0), axis(1), axis(2)),
+        }
+    }
+}
+
+impl Hittable for Plane {
+    fn hit_tmin_tmax(&self, r: &Ray, ray_tmin: f64, ray_tmax: f64, rec: &mut HitRecord) -> bool {
+        self.hit_interval(r, Interval::new(ray_tmin, ray_tmax), rec)
+    }
+
+    fn hit_interval(&self, r: &Ray, ray_t: Interval, rec: &mut HitRecord) -> bool {
+        let denom = dot(self.normal, r.direction());
+
+        // The ray is parallel to the plane
+        if denom.abs() < 1e-8 {
+            return false;
+        }
+
+        let t = dot(self.normal, self.point - r.origin()) / denom;
+        if !ray_t.surrounds(t) {
+            return false;
+        }
+
+        rec.t = t;
+        rec.p = r.at(t);
+        let offset = rec.p - self.point;
+        rec.u = dot(offset, self.frame.u()).rem_euclid(1.0);
+        rec.v = dot(offset, self.frame.v()).rem_euclid(1.0);
+        rec.tangent = self.frame.u();
+        rec.mat = self.mat.clone();
+        rec.set_face_normal(r, &self.normal);
+
+        true
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.bbox
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{DefaultMaterial, INFINITY};
+
+    #[test]
+    fn test_hit_from_both_sides() {
+        let plane = Plane::new(
+            Point3::new(0.0, -1.0, 0.0),
+            Vec3::new(0.0, 2.0, 0.0),
+            Arc::new(DefaultMaterial::new()),
+        );
+        let mut rec = HitRecord::new();
+
+        let down = Ray::new(Point3::new(3.25, 1.0, -7.5), Vec3::new(0.0, -1.0, 0.0));
+        assert!(plane.hit_interval(&down, Interval::new(0.001, INFINITY), &mut rec));
+        assert!((rec.t - 2.0).abs() < 1e-12);
+        assert!(rec.front_face);
+        assert_eq!(rec.normal, Vec3::new(0.0, 1.0, 0.0));
+        assert!((0.0..1.0).contains(&rec.u) && (0.0..1.0).contains(&rec.v));
+
+        let up = Ray::new(Point3::new(0.0, -3.0, 0.0), Vec3::new(0.0, 1.0, 0.0));
+        assert!(plane.hit_interval(&up, Interval::new(0.001, INFINITY), &mut rec));
+        assert!(!rec.front_face);
+        assert_eq!(rec.normal, Vec3::new(0.0, -1.0, 0.0));
+
+        let parallel = Ray::new(Point3::new(0.0, 1.0, 0.0), Vec3::new(1.0, 0.0, 0.0));
+        assert!(!plane.hit_interval(&parallel, Interval::new(0.001, INFINITY), &mut rec));
+
+        // Axis-aligned planes get a flat, finite bounding box
+        assert!(plane.bounding_box().y.size() < 0.001);
+        assert!(plane.bounding_box().x.size().is_finite());
+
+        // A tilted plane far from the origin is still hit, and its box holds the hit point
+        let far = Point3::new(5e6, 0.0, -5e6);
+        let tilted = Plane::new(
+            far,
+            Vec3::new(1.0, 1.0, 0.0),
+            Arc::new(DefaultMaterial::new()),
+        );
+        let r = Ray::new(far + Vec3::new(0.0, 10.0, 0.0), Vec3::new(0.0, -1.0, 0.0));
+        assert!(tilted
+            .bounding_box()
+            .hit(&r, Interval::new(0.001, INFINITY)));
+        assert!(tilted.hit_interval(&r, Interval::new(0.001, INFINITY), &mut rec));
+        assert!(rec.front_face);
+        assert!((rec.t - 10.0).abs() < 1e-6);
+        let bbox = tilted.bounding_box();
+        assert!((0..3).all(|i| bbox.axis_interval(i).contains(rec.p[i])));
+    }
+}
@@ -0,0 +1,198 @@
+use crate::{
+    dot, unit_vector, vec3::cross, Aabb, HitRecord, Hittable, HittableList, Interval, Material,
+    Point3, Ray, Sampler, ShapeSample, Vec3, INFINITY,
+};
+use std::sync::Arc;
+
+// Parallelogram spanned by the edges u and v from the corner q
+pub struct Quad {
+    q: Point3,
+    u: Vec3,
+    v: Vec3,
+    w: Vec3,      // n / (n . n), used to find the planar coordinates of a hit point
+    normal: Vec3, // Unit normal, along u x v
+    d: f64,       // Plane offset, normal . p = d
+    area: f64,
+    mat: Arc<dyn Material>,
+    bbox: Aabb,
+}
+
+impl Quad {
+    pub fn new(q: Point3, u: Vec3, v: Vec3, mat: Arc<dyn Material>) -> Self {
+        let n = cross(u, v);
+        let normal = unit_vector(n);
+        let bbox_diagonal1 = Aabb::from_points(q, q + u + v);
+        let bbox_diagonal2 = Aabb::from_points(q + u, q + v);
+        Self {
+            q,
+            u,
+            v,
+            w: n / dot(n, n),
+            normal,
+            d: dot(normal, q),
+            area: n.length(),
+            mat,
+            bbox: Aabb::surrounding(&bbox_diagonal1, &bbox_diagonal2),
+        }
+    }
+}
+
+impl Hittable for Quad {
+    fn hit_tmin_tmax(&self, r: &Ray, ray_tmin: f64, ray_tmax: f64, rec: &mut HitRecord) -> bool {
+        self.hit_interval(r, Interval::new(ray_tmin, ray_tmax), rec)
+    }
+
+    fn hit_interval(&self, r: &Ray, ray_t: Interval, rec: &mut HitRecord) -> bool {
+        let denom = dot(self.normal, r.direction());
+
+        // The ray is parallel to the plane
+        if denom.abs() < 1e-8 {
+            return false;
+        }
+
+        let t = (self.d - dot(self.normal, r.origin())) / denom;
+        if !ray_t.contains(t) {
+            return false;
+        }
+
+        // Planar coordinates of the hit point along u and v
+        let intersection = r.at(t);
+        let planar_hitpt_vector = intersection - self.q;
+        let alpha = dot(self.w, cross(planar_hitpt_vector, self.v));
+        let beta = dot(self.w, cross(self.u, planar_hitpt_vector));
+        if !(0.0..=1.0).contains(&alpha) || !(0.0..=1.0).contains(&beta) {
+            return false;
+        }
+
+        rec.t = t;
+        rec.p = intersection;
+        rec.u = alpha;
+        rec.v = beta;
+        rec.tangent = self.u;
+        rec.mat = self.mat.clone();
+        rec.set_face_normal(r, &self.normal);
+
+        true
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.bbox
+    }
+
+    // Uniformly sample the area of the quad
+    fn sample(&self, origin: &Point3, time: f64, sampler: &mut dyn Sampler) -> Option<ShapeSample> {
+        let (a, b) = sampler.get_2d();
+        let p = self.q + a * self.u + b * self.v;
+        let pdf = self.pdf_value(&Ray::new_with_time(*origin, p - *origin, time));
+        (pdf > 0.0).then_some(ShapeSample { p, pdf })
+    }
+
+    fn pdf_value(&self, r: &Ray) -> f64 {
+        let mut rec = HitRecord::new();
+        if !self.hit_interval(r, Interval::new(0.001, INFINITY), &mut rec) {
+            return 0.0;
+        }
+
+        // Convert the area density 1 / area to a density over solid angle
+        let distance_squared = rec.t * rec.t * r.direction().length_squared();
+        let cosine = dot(r.direction(), self.normal).abs() / r.direction().length();
+        if cosine < 1e-8 {
+            return 0.0;
+        }
+        distance_squared / (cosine * self.area)
+    }
+}
+
+// The six quads of the box with opposite corners a and b, normals facing out
+pub fn make_box(a: Point3, b: Point3, mat: Arc<dyn Material>) -> HittableList {
+    let min = Point3::new(a.x().min(b.x()), a.y().min(b.y()), a.z().min(b.z()));
+    let max = Point3::new(a.x().max(b.x()), a.y().max(b.y()), a.z().max(b.z()));
+
+    let dx = Vec3::new(max.x() - min.x(), 0.0, 0.0);
+    let dy = Vec3::new(0.0, max.y() - min.y(), 0.0);
+    let dz = Vec3::new(0.0, 0.0, max.z() - min.z());
+
+    let mut sides = HittableList::new();
+    let faces = [
+        (Point3::new(min.x(), min.y(), max.z()), dx, dy), // Front
+        (Point3::new(max.x(), min.y(), max.z()), -dz, dy), // Right
+        (Point3::new(max.x(), min.y(), min.z()), -dx, dy), // Back
+        (Point3::new(min.x(), min.y(), min.z()), dz, dy), // Left
+        (Point3::new(min.x(), max.y(), max.z()), dx, -dz), // Top
+        (Point3::new(min.x(), min.y(), min.z()), dx, dz), // Bottom
+    ];
+    for (q, u, v) in faces {
+        sides.add(Arc::new(Quad::new(q, u, v, mat.clone())));
+    }
+    sides
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        hittable::assert_sample_density_matches_solid_angle, DefaultMaterial, IndependentSampler,
+    };
+
+    fn unit_square() -> Quad {
+        Quad::new(
+            Point3::new(-0.5, -0.5, -1.0),
+            Vec3::new(1.0, 0.0, 0.0),
+            Vec3::new(0.0, 1.0, 0.0),
+            Arc::new(DefaultMaterial::new()),
+        )
+    }
+
+    #[test]
+    fn test_hit_sets_uv_and_normal() {
+        let quad = unit_square();
+        let r = Ray::new(Point3::new(0.25, -0.25, 0.0), Vec3::new(0.0, 0.0, -1.0));
+        let mut rec = HitRecord::new();
+
+        assert!(quad.hit_interval(&r, Interval::new(0.001, INFINITY), &mut rec));
+        assert!((rec.t - 1.0).abs() < 1e-12);
+        assert!((rec.u - 0.75).abs() < 1e-12);
+        assert!((rec.v - 0.25).abs() < 1e-12);
+        assert!(rec.front_face);
+        assert_eq!(rec.normal, Vec3::new(0.0, 0.0, 1.0));
+
+        let miss = Ray::new(Point3::new(0.75, 0.0, 0.0), Vec3::new(0.0, 0.0, -1.0));
+        assert!(!quad.hit_interval(&miss, Interval::new(0.001, INFINITY), &mut rec));
+    }
+
+    #[test]
+    fn test_sample_density_matches_solid_angle() {
+        let mut sampler = IndependentSampler::new(3);
+        assert_sample_density_matches_solid_angle(
+            &unit_square(),
+            Point3::new(0.0, 0.0, 0.0),
+            &mut sampler,
+        );
+    }
+
+    #[test]
+    fn test_box_normals_face_out() {
+        let cube = make_box(
+            Point3::new(1.0, 1.0, 1.0),
+            Point3::new(-1.0, -1.0, -1.0),
+            Arc::new(DefaultMaterial::new()),
+        );
+        assert_eq!(cube.objects().len(), 6);
+
+        // A ray from outside towards the center enters through a front face
+        for dir in [
+            Vec3::new(1.0, 0.0, 0.0),
+            Vec3::new(0.0, 1.0, 0.0),
+            Vec3::new(0.0, 0.0, 1.0),
+        ] {
+            for sign in [-1.0, 1.0] {
+                let r = Ray::new(5.0 * sign * dir, -sign * dir);
+                let mut rec = HitRecord::new();
+                assert!(cube.hit_interval(&r, Interval::new(0.001, INFINITY), &mut rec));
+                assert!((rec.t - 4.0).abs() < 1e-12);
+                assert!(rec.front_face);
+                assert_eq!(rec.normal, sign * dir);
+            }
+        }
+    }
+}
@@ -3,13 +3,18 @@ use crate::{Point3, Vec3};
 pub struct Ray {
     orig: Point3,
     dir: Vec3,
+    tm: f64,
 }
 
 impl Ray {
     pub fn new(origin: Point3, direction: Vec3) -> Self {
+        Self::new_with_time(origin, direction, 0.0)
+    }
+    pub fn new_with_time(origin: Point3, direction: Vec3, time: f64) -> Self {
         Self {
             orig: origin,
             dir: direction,
+            tm: time,
         }
     }
     pub fn origin(&self) -> Point3 {
@@ -18,6 +23,9 @@ impl Ray {
     pub fn direction(&self) -> Vec3 {
         self.dir
     }
+    pub fn time(&self) -> f64 {
+        self.tm
+    }
 
     pub fn at(&self, t: f64) -> Point3 {
         self.orig + self.dir * t
@@ -0,0 +1,472 @@
+use crate::{utility::hash_seed, Rng};
+
+// Source of the random numbers used to render one pixel sample. The camera positions the
+// sampler on a (pixel, sample index) pair before tracing, so every value drawn afterwards
+// depends only on the render seed and that pair.
+pub trait Sampler {
+    fn start_pixel_sample(&mut self, i: u32, j: u32, sample_index: u32);
+
+    // Next dimension of the current sample, in [0, 1)
+    fn get_1d(&mut self) -> f64;
+
+    // Next two dimensions of the current sample, in [0, 1)^2
+    fn get_2d(&mut self) -> (f64, f64) {
+        let x = self.get_1d();
+        (x, self.get_1d())
+    }
+
+    // Unstructured draw in [0, 1), used where the number of draws is not fixed (rejection
+    // sampling, random permutations)
+    fn random_double(&mut self) -> f64;
+
+    fn random_double_range(&mut self, min: f64, max: f64) -> f64 {
+        min + (max - min) * self.random_double()
+    }
+}
+
+// Which Sampler the camera builds for each render thread
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SamplerType {
+    #[default]
+    Independent, // Uniform random numbers
+    Stratified, // Jittered strata, shuffled independently per dimension
+    Halton,     // Halton sequence with random digit permutations per pixel
+    Sobol,      // Owen-scrambled Sobol (0,2) sequence, padded across dimension pairs
+}
+
+impl SamplerType {
+    pub fn create(self, seed: u64, samples_per_pixel: u32) -> Box<dyn Sampler> {
+        match self {
+            SamplerType::Independent => Box::new(IndependentSampler::new(seed)),
+            SamplerType::Stratified => Box::new(StratifiedSampler::new(seed, samples_per_pixel)),
+            SamplerType::Halton => Box::new(HaltonSampler::new(seed)),
+            SamplerType::Sobol => Box::new(SobolSampler::new(seed)),
+        }
+    }
+}
+
+// Position within the sample vector, shared by every sampler
+struct SampleState {
+    seed: u64,
+    pixel_seed: u64, // Hash of the render seed and the pixel
+    sample_index: u32,
+    dimension: u32,
+    rng: Rng,
+}
+
+impl SampleState {
+    fn new(seed: u64) -> Self {
+        Self {
+            seed,
+            pixel_seed: seed,
+            sample_index: 0,
+            dimension: 0,
+            rng: Rng::new(seed),
+        }
+    }
+
+    fn start(&mut self, i: u32, j: u32, sample_index: u32) {
+        self.pixel_seed = hash_seed(&[self.seed, i as u64, j as u64]);
+        self.sample_index = sample_index;
+        self.dimension = 0;
+        self.rng = Rng::new(hash_seed(&[self.pixel_seed, sample_index as u64]));
+    }
+
+    // Claim the next `count` dimensions and return the first one
+    fn next_dimensions(&mut self, count: u32) -> u32 {
+        let dimension = self.dimension;
+        self.dimension += count;
+        dimension
+    }
+
+    fn dimension_seed(&self, dimension: u32) -> u64 {
+        hash_seed(&[self.pixel_seed, dimension as u64])
+    }
+}
+
+// Plain uniform random numbers, reseeded for every pixel sample
+pub struct IndependentSampler {
+    state: SampleState,
+}
+
+impl IndependentSampler {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            state: SampleState::new(seed),
+        }
+    }
+}
+
+impl Sampler for IndependentSampler {
+    fn start_pixel_sample(&mut self, i: u32, j: u32, sample_index: u32) {
+        self.state.start(i, j, sample_index);
+    }
+
+    fn get_1d(&mut self) -> f64 {
+        self.state.rng.random_double()
+    }
+
+    fn random_double(&mut self) -> f64 {
+        self.state.rng.random_double()
+    }
+}
+
+// Splits every dimension into one stratum per sample and jitters within it. Each dimension
+// visits the strata in its own random order so dimensions stay uncorrelated. 2D samples use a
+// jittered grid when the sample count is a square, and Latin hypercube strata otherwise.
+pub struct StratifiedSampler {
+    state: SampleState,
+    samples_per_pixel: u32,
+    grid_size: Option<u32>, // Side of the 2D grid, when samples_per_pixel is a square
+}
+
+impl StratifiedSampler {
+    pub fn new(seed: u64, samples_per_pixel: u32) -> Self {
+        let samples_per_pixel = samples_per_pixel.max(1);
+        let side = (samples_per_pixel as f64).sqrt().round() as u32;
+        Self {
+            state: SampleState::new(seed),
+            samples_per_pixel,
+            grid_size: (side * side == samples_per_pixel).then_some(side),
+        }
+    }
+
+    // Stratum of the current sample along the given dimension. Samples past
+    // samples_per_pixel wrap around onto the same strata.
+    fn stratum(&self, dimension: u32) -> u32 {
+        let index = self.state.sample_index % self.samples_per_pixel;
+        let seed = self.state.dimension_seed(dimension) as u32;
+        permutation_element(index, self.samples_per_pixel, seed)
+    }
+
+    fn jittered(&mut self, stratum: u32, count: u32) -> f64 {
+        (stratum as f64 + self.state.rng.random_double()) / count as f64
+    }
+}
+
+impl Sampler for StratifiedSampler {
+    fn start_pixel_sample(&mut self, i: u32, j: u32, sample_index: u32) {
+        self.state.start(i, j, sample_index);
+    }
+
+    fn get_1d(&mut self) -> f64 {
+        let dimension = self.state.next_dimensions(1);
+        let stratum = self.stratum(dimension);
+        self.jittered(stratum, self.samples_per_pixel)
+    }
+
+    fn get_2d(&mut self) -> (f64, f64) {
+        let dimension = self.state.next_dimensions(2);
+        match self.grid_size {
+            Some(side) => {
+                let cell = self.stratum(dimension);
+                (
+                    self.jittered(cell % side, side),
+                    self.jittered(cell / side, side),
+                )
+            }
+            None => {
+                let (sx, sy) = (self.stratum(dimension), self.stratum(dimension + 1));
+                (
+                    self.jittered(sx, self.samples_per_pixel),
+                    self.jittered(sy, self.samples_per_pixel),
+                )
+            }
+        }
+    }
+
+    fn random_double(&mut self) -> f64 {
+        self.state.rng.random_double()
+    }
+}
+
+// Bases of the Halton dimensions; later dimensions fall back to uniform random numbers
+const HALTON_PRIMES: [u32; 32] = [
+    2, 3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37, 41, 43, 47, 53, 59, 61, 67, 71, 73, 79, 83, 89, 97,
+    101, 103, 107, 109, 113, 127, 131,
+];
+
+// Halton sequence indexed by the sample number. Each pixel permutes the digits of every
+// dimension with its own random permutations, which removes the correlation between pixels
+// and between high dimensions.
+pub struct HaltonSampler {
+    state: SampleState,
+}
+
+impl HaltonSampler {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            state: SampleState::new(seed),
+        }
+    }
+
+    fn scrambled_radical_inverse(&self, dimension: u32) -> f64 {
+        let base = HALTON_PRIMES[dimension as usize];
+        let dimension_seed = self.state.dimension_seed(dimension);
+        let inv_base = 1.0 / base as f64;
+        let mut index = self.state.sample_index;
+        let mut inv_base_m = 1.0;
+        let mut result = 0.0;
+        let mut digit_index = 0;
+
+        // Permuting the trailing zero digits too keeps the points off the stratum edges
+        while inv_base_m > 1e-16 {
+            let digit = index % base;
+            let digit_seed = hash_seed(&[dimension_seed, digit_index]) as u32;
+            inv_base_m *= inv_base;
+            result += permutation_element(digit, base, digit_seed) as f64 * inv_base_m;
+            index /= base;
+            digit_index += 1;
+        }
+        result.min(ONE_MINUS_EPSILON)
+    }
+}
+
+impl Sampler for HaltonSampler {
+    fn start_pixel_sample(&mut self, i: u32, j: u32, sample_index: u32) {
+        self.state.start(i, j, sample_index);
+    }
+
+    fn get_1d(&mut self) -> f64 {
+        let dimension = self.state.next_dimensions(1);
+        if dimension as usize >= HALTON_PRIMES.len() {
+            return self.state.rng.random_double();
+        }
+        self.scrambled_radical_inverse(dimension)
+    }
+
+    fn random_double(&mut self) -> f64 {
+        self.state.rng.random_double()
+    }
+}
+
+// The first two dimensions of the Sobol sequence, Owen-scrambled with a hash (Burley 2020).
+// Every pair of dimensions shuffles the sample order and scrambles the points with its own
+// seed, so higher dimensions are padded from independent copies of the 2D sequence.
+pub struct SobolSampler {
+    state: SampleState,
+}
+
+impl SobolSampler {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            state: SampleState::new(seed),
+        }
+    }
+
+    fn sample(&self, dimension: u32) -> (f64, f64) {
+        let seed = self.state.dimension_seed(dimension);
+        let index = nested_uniform_scramble(self.state.sample_index, seed as u32);
+        let x = nested_uniform_scramble(index.reverse_bits(), (seed >> 32) as u32);
+        let y = nested_uniform_scramble(sobol_second_dimension(index), hash_seed(&[seed]) as u32);
+        (to_unit_float(x), to_unit_float(y))
+    }
+}
+
+impl Sampler for SobolSampler {
+    fn start_pixel_sample(&mut self, i: u32, j: u32, sample_index: u32) {
+        self.state.start(i, j, sample_index);
+    }
+
+    fn get_1d(&mut self) -> f64 {
+        let dimension = self.state.next_dimensions(1);
+        self.sample(dimension).0
+    }
+
+    fn get_2d(&mut self) -> (f64, f64) {
+        let dimension = self.state.next_dimensions(2);
+        self.sample(dimension)
+    }
+
+    fn random_double(&mut self) -> f64 {
+        self.state.rng.random_double()
+    }
+}
+
+// Largest f64 below one
+const ONE_MINUS_EPSILON: f64 = 1.0 - f64::EPSILON / 2.0;
+
+fn to_unit_float(x: u32) -> f64 {
+    x as f64 / (1u64 << 32) as f64
+}
+
+// Second Sobol dimension; the first is the bit-reversed index
+fn sobol_second_dimension(mut index: u32) -> u32 {
+    let mut result = 0;
+    let mut v = 1u32 << 31;
+    while index != 0 {
+        if index & 1 != 0 {
+            result ^= v;
+        }
+        index >>= 1;
+        v ^= v >> 1;
+    }
+    result
+}
+
+// Hash-based Owen scrambling: each bit is flipped depending only on the bits above it
+fn nested_uniform_scramble(x: u32, seed: u32) -> u32 {
+    laine_karras_permutation(x.reverse_bits(), seed).reverse_bits()
+}
+
+fn laine_karras_permutation(mut x: u32, seed: u32) -> u32 {
+    x = x.wrapping_add(seed);
+    x ^= x.wrapping_mul(0x6C50_B47C);
+    x ^= x.wrapping_mul(0xB82F_1E52);
+    x ^= x.wrapping_mul(0xC7AF_E638);
+    x ^= x.wrapping_mul(0x8D22_F6E6);
+    x
+}
+
+// Element i of a random permutation of 0..n chosen by the seed, computed without storing the
+// permutation (Kensler, "Correlated Multi-Jittered Sampling")
+fn permutation_element(mut i: u32, n: u32, seed: u32) -> u32 {
+    if n <= 1 {
+        return 0;
+    }
+    let mut w = n - 1;
+    w |= w >> 1;
+    w |= w >> 2;
+    w |= w >> 4;
+    w |= w >> 8;
+    w |= w >> 16;
+    loop {
+        i ^= seed;
+        i = i.wrapping_mul(0xE170_893D);
+        i ^= seed >> 16;
+        i ^= (i & w) >> 4;
+        i ^= seed >> 8;
+        i = i.wrapping_mul(0x0929_EB3F);
+        i ^= seed >> 23;
+        i ^= (i & w) >> 1;
+        i = i.wrapping_mul(1 | seed >> 27);
+        i = i.wrapping_mul(0x6935_FA69);
+        i ^= (i & w) >> 11;
+        i = i.wrapping_mul(0x74DC_B303);
+        i ^= (i & w) >> 2;
+        i = i.wrapping_mul(0x9E50_1CC3);
+        i ^= (i & w) >> 2;
+        i = i.wrapping_mul(0xC860_A3DF);
+        i &= w;
+        i ^= i >> 5;
+        if i < n {
+            break;
+        }
+    }
+    (i.wrapping_add(seed)) % n
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ALL_TYPES: [SamplerType; 4] = [
+        SamplerType::Independent,
+        SamplerType::Stratified,
+        SamplerType::Halton,
+        SamplerType::Sobol,
+    ];
+
+    fn draws(sampler: &mut dyn Sampler, i: u32, j: u32, sample_index: u32) -> Vec<f64> {
+        sampler.start_pixel_sample(i, j, sample_index);
+        (0..8).map(|_| sampler.get_1d()).collect()
+    }
+
+    #[test]
+    fn test_pixel_sample_is_reproducible() {
+        for kind in ALL_TYPES {
+            let mut a = kind.create(42, 16);
+            let mut b = kind.create(42, 16);
+            let first = draws(a.as_mut(), 3, 5, 7);
+            draws(b.as_mut(), 0, 0, 0);
+            assert_eq!(first, draws(b.as_mut(), 3, 5, 7));
+            assert!(first.iter().all(|x| (0.0..1.0).contains(x)));
+        }
+    }
+
+    #[test]
+    fn test_pixel_samples_differ() {
+        for kind in ALL_TYPES {
+            let mut s = kind.create(42, 16);
+            let base = draws(s.as_mut(), 3, 5, 7);
+            assert_ne!(base, draws(s.as_mut(), 3, 5, 8));
+            assert_ne!(base, draws(s.as_mut(), 5, 3, 7));
+            assert_ne!(base, draws(kind.create(43, 16).as_mut(), 3, 5, 7));
+        }
+    }
+
+    #[test]
+    fn test_permutation_element_is_a_permutation() {
+        for n in [1, 2, 5, 16, 100] {
+            let mut seen: Vec<u32> = (0..n).map(|i| permutation_element(i, n, 1234)).collect();
+            seen.sort();
+            assert_eq!(seen, (0..n).collect::<Vec<_>>());
+        }
+    }
+
+    // Draw `count` samples of one dimension and check each of `count` strata gets exactly one
+    fn assert_stratified(kind: SamplerType, dimension: u32, count: u32) {
+        let mut sampler = kind.create(9, count);
+        let mut strata = vec![0; count as usize];
+        for s in 0..count {
+            sampler.start_pixel_sample(2, 3, s);
+            for _ in 0..dimension {
+                sampler.get_1d();
+            }
+            strata[(sampler.get_1d() * count as f64) as usize] += 1;
+        }
+        assert!(
+            strata.iter().all(|&n| n == 1),
+            "{kind:?} dimension {dimension}"
+        );
+    }
+
+    #[test]
+    fn test_samples_cover_every_stratum() {
+        for dimension in 0..4 {
+            assert_stratified(SamplerType::Stratified, dimension, 10);
+            assert_stratified(SamplerType::Sobol, dimension, 16);
+            // Dimension d of the Halton sequence is stratified on powers of its prime base
+            let base = HALTON_PRIMES[dimension as usize];
+            assert_stratified(SamplerType::Halton, dimension, base * base);
+        }
+    }
+
+    // Root mean square error of estimating the area of the quarter disk x^2 + y^2 < 1 over
+    // many pixels, at equal sample counts
+    fn quarter_disk_error(kind: SamplerType, samples_per_pixel: u32) -> f64 {
+        let mut sampler = kind.create(5, samples_per_pixel);
+        let exact = std::f64::consts::PI / 4.0;
+        let pixels = 256;
+        let mut squared_error = 0.0;
+        for pixel in 0..pixels {
+            let mut inside = 0;
+            for s in 0..samples_per_pixel {
+                sampler.start_pixel_sample(pixel, 0, s);
+                let (x, y) = sampler.get_2d();
+                if x * x + y * y < 1.0 {
+                    inside += 1;
+                }
+            }
+            let estimate = inside as f64 / samples_per_pixel as f64;
+            squared_error += (estimate - exact).powi(2);
+        }
+        (squared_error / pixels as f64).sqrt()
+    }
+
+    #[test]
+    fn test_low_discrepancy_samplers_reduce_error() {
+        let independent = quarter_disk_error(SamplerType::Independent, 64);
+        for kind in [
+            SamplerType::Stratified,
+            SamplerType::Halton,
+            SamplerType::Sobol,
+        ] {
+            let error = quarter_disk_error(kind, 64);
+            assert!(
+                error < 0.5 * independent,
+                "{kind:?}: {error} vs {independent}"
+            );
+        }
+    }
+}
@@ -0,0 +1,1405 @@
+use crate::{
+    load_obj, load_raw, load_vol, make_box, principled::scalar, vec3::cross, AdaptiveSampling,
+    Background, Camera, CheckerTexture, Conductor, ConductorPreset, ConstantMedium,
+    DefaultMaterial, Dielectric, DiffuseLight, Disk, GridMedium, HenyeyGreenstein, Hittable,
+    HittableList, ImageTexture, Instance, Isotropic, Lambertian, Mat4, Material, Metal,
+    NoiseTexture, ObjError, Plane, Point3, Principled, Quad, SamplerType, SolidColor, Sphere,
+    Texture, ToneMapOperator, ToneMapper, Triangle, Vec3, VolumeError,
+};
+use serde::Deserialize;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+// A camera and the world it looks at, as described by a scene file
+pub struct Scene {
+    pub camera: Camera,
+    pub world: HittableList,
+    pub lights: HittableList, // Emissive spheres and quads of the world, sampled directly
+    pub tone_mapper: ToneMapper, // Applied when saving to 8-bit formats
+}
+
+#[derive(Debug)]
+pub enum SceneError {
+    Io {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    Parse {
+        path: PathBuf,
+        message: String,
+    },
+    // A well-formed entry whose values do not make sense, e.g. "objects[2]"
+    Invalid {
+        entry: String,
+        message: String,
+    },
+    Obj {
+        entry: String,
+        source: ObjError,
+    },
+    Volume {
+        entry: String,
+        source: VolumeError,
+    },
+}
+
+impl fmt::Display for SceneError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SceneError::Io { path, source } => write!(f, "{}: {}", path.display(), source),
+            SceneError::Parse { path, message } => write!(f, "{}: {}", path.display(), message),
+            SceneError::Invalid { entry, message } => write!(f, "{}: {}", entry, message),
+            SceneError::Obj { entry, source } => write!(f, "{}: {}", entry, source),
+            SceneError::Volume { entry, source } => write!(f, "{}: {}", entry, source),
+        }
+    }
+}
+
+impl std::error::Error for SceneError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            SceneError::Io { source, .. } => Some(source),
+            SceneError::Obj { source, .. } => Some(source),
+            SceneError::Volume { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
+
+// Load a TOML scene file. Relative paths inside it are resolved from the file's directory.
+pub fn load_scene(path: impl AsRef<Path>) -> Result<Scene, SceneError> {
+    let path = path.as_ref();
+    let source = fs::read_to_string(path).map_err(|source| SceneError::Io {
+        path: path.to_path_buf(),
+        source,
+    })?;
+    parse_scene(&source, path)
+}
+
+fn parse_scene(source: &str, path: &Path) -> Result<Scene, SceneError> {
+    let description: SceneDescription = toml::from_str(source).map_err(|e| SceneError::Parse {
+        path: path.to_path_buf(),
+        message: e.to_string(),
+    })?;
+    description.build(path.parent().unwrap_or_else(|| Path::new("")))
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct SceneDescription {
+    #[serde(default)]
+    camera: CameraDescription,
+    #[serde(default)]
+    textures: BTreeMap<String, TextureDescription>,
+    #[serde(default)]
+    materials: BTreeMap<String, MaterialDescription>,
+    #[serde(default)]
+    objects: Vec<ObjectDescription>,
+    #[serde(default)]
+    tone_mapping: ToneMappingDescription,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(deny_unknown_fields)]
+struct ToneMappingDescription {
+    exposure: Option<f64>,
+    operator: Option<OperatorName>,
+    white_point: Option<f64>, // Only used by reinhard_extended
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum OperatorName {
+    Clamp,
+    Reinhard,
+    ReinhardExtended,
+    Aces,
+    Hable,
+}
+
+// Every field is optional and falls back to the Camera::new default
+#[derive(Deserialize, Default)]
+#[serde(deny_unknown_fields)]
+struct CameraDescription {
+    aspect_ratio: Option<f64>,
+    image_width: Option<u32>,
+    samples_per_pixel: Option<u32>,
+    max_depth: Option<u32>,
+    roulette_depth: Option<u32>,
+    fvov: Option<f64>,
+    lookfrom: Option<[f64; 3]>,
+    lookat: Option<[f64; 3]>,
+    vup: Option<[f64; 3]>,
+    defocus_angle: Option<f64>,
+    focus_dist: Option<f64>,
+    shutter_open: Option<f64>,
+    shutter_close: Option<f64>,
+    thread_count: Option<usize>,
+    seed: Option<u64>,
+    sampler: Option<SamplerName>,
+    background: Option<BackgroundDescription>,
+    adaptive: Option<AdaptiveDescription>,
+}
+
+// Missing fields fall back to AdaptiveSampling::default
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct AdaptiveDescription {
+    threshold: Option<f64>,
+    min_samples: Option<u32>,
+    max_samples: Option<u32>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum SamplerName {
+    Independent,
+    Stratified,
+    Halton,
+    Sobol,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum ConductorName {
+    Gold,
+    Copper,
+    Aluminum,
+    Silver,
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+enum BackgroundDescription {
+    None,
+    Solid { color: [f64; 3] },
+    Gradient { bottom: [f64; 3], top: [f64; 3] },
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+enum TextureDescription {
+    Solid {
+        color: [f64; 3],
+    },
+    Checker {
+        scale: f64,
+        even: [f64; 3],
+        odd: [f64; 3],
+    },
+    Image {
+        path: PathBuf,
+    },
+    Noise {
+        scale: f64,
+    },
+}
+
+// Material colors are either a constant or the name of an entry in [textures]
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum ColorOrTexture {
+    Color([f64; 3]),
+    Texture(String),
+}
+
+// Scalar material parameters likewise, reading the mean of a texture's channels
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum ScalarOrTexture {
+    Value(f64),
+    Texture(String),
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+enum MaterialDescription {
+    Lambertian {
+        albedo: ColorOrTexture,
+    },
+    Metal {
+        albedo: ColorOrTexture,
+        fuzz: f64,
+    },
+    // Either a preset or eta and k. roughness_u and roughness_v override roughness.
+    Conductor {
+        preset: Option<ConductorName>,
+        eta: Option<[f64; 3]>,
+        k: Option<[f64; 3]>,
+        roughness: Option<f64>,
+        roughness_u: Option<f64>,
+        roughness_v: Option<f64>,
+    },
+    Dielectric {
+        refraction_index: f64,
+        roughness: Option<f64>,
+        absorption_color: Option<[f64; 3]>, // Color left after absorption_distance inside
+        absorption_distance: Option<f64>,
+        exact_fresnel: Option<bool>,
+    },
+    // Unset parameters keep Principled's defaults
+    Principled {
+        base_color: ColorOrTexture,
+        metallic: Option<ScalarOrTexture>,
+        roughness: Option<ScalarOrTexture>,
+        specular: Option<ScalarOrTexture>,
+        sheen: Option<ScalarOrTexture>,
+        clearcoat: Option<ScalarOrTexture>,
+        clearcoat_roughness: Option<ScalarOrTexture>,
+        transmission: Option<ScalarOrTexture>,
+        refraction_index: Option<f64>,
+    },
+    DiffuseLight {
+        emit: ColorOrTexture,
+    },
+    // Phase function for constant_medium objects
+    Isotropic {
+        albedo: ColorOrTexture,
+    },
+    HenyeyGreenstein {
+        albedo: ColorOrTexture,
+        g: f64,
+    },
+}
+
+type Textures = BTreeMap<String, Arc<dyn Texture>>;
+
+// Objects built so far that have a name, and whether each is a sampled light
+type NamedObjects = BTreeMap<String, (Arc<dyn Hittable>, bool)>;
+
+// Surfaces need a material, except as the boundary of a constant medium
+#[derive(Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+enum ObjectDescription {
+    Sphere {
+        name: Option<String>,
+        center: [f64; 3],
+        center2: Option<[f64; 3]>, // Center at time 1 for a moving sphere
+        radius: f64,
+        material: Option<String>,
+    },
+    Triangle {
+        name: Option<String>,
+        vertices: [[f64; 3]; 3],
+        material: Option<String>,
+    },
+    Quad {
+        name: Option<String>,
+        corner: [f64; 3],
+        u: [f64; 3], // First edge from the corner
+        v: [f64; 3], // Second edge from the corner
+        material: Option<String>,
+    },
+    Box {
+        name: Option<String>,
+        min: [f64; 3], // Opposite corners
+        max: [f64; 3],
+        material: Option<String>,
+    },
+    Plane {
+        name: Option<String>,
+        point: [f64; 3],
+        normal: [f64; 3],
+        material: Option<String>,
+    },
+    Disk {
+        name: Option<String>,
+        center: [f64; 3],
+        normal: [f64; 3],
+        radius: f64,
+        material: Option<String>,
+    },
+    Obj {
+        name: Option<String>,
+        path: PathBuf,
+    },
+    // Volume of uniform density filling a closed boundary shape, which needs no material
+    ConstantMedium {
+        name: Option<String>,
+        boundary: Box<ObjectDescription>,
+        density: f64,
+        material: String,
+    },
+    // Density grid from a .vol file, or from raw 32-bit floats given a resolution. The bounds
+    // default to those in the .vol header, or the unit cube for raw files.
+    GridMedium {
+        name: Option<String>,
+        path: PathBuf,
+        resolution: Option<[usize; 3]>,
+        min: Option<[f64; 3]>,
+        max: Option<[f64; 3]>,
+        density: f64, // Scale applied to the grid values
+        material: String,
+    },
+    // Another copy of an earlier named object, placed by transforms applied in order
+    Instance {
+        name: Option<String>,
+        object: String,
+        transforms: Vec<TransformDescription>,
+    },
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+enum TransformDescription {
+    Translate {
+        offset: [f64; 3],
+    },
+    Rotate {
+        axis: [f64; 3],
+        degrees: f64,
+    },
+    Scale {
+        factors: [f64; 3],
+    },
+    LookAt {
+        from: [f64; 3],
+        target: [f64; 3],
+        up: [f64; 3],
+    },
+}
+
+impl SceneDescription {
+    fn build(self, base_dir: &Path) -> Result<Scene, SceneError> {
+        let camera = self.camera.build()?;
+        let tone_mapper = self.tone_mapping.build()?;
+
+        let mut textures: Textures = BTreeMap::new();
+        for (name, description) in self.textures {
+            let entry = format!("textures.{}", name);
+            textures.insert(name, description.build(&entry, base_dir)?);
+        }
+
+        let mut materials: BTreeMap<String, Arc<dyn Material>> = BTreeMap::new();
+        let mut light_materials = BTreeSet::new();
+        for (name, description) in self.materials {
+            let entry = format!("materials.{}", name);
+            if matches!(description, MaterialDescription::DiffuseLight { .. }) {
+                light_materials.insert(name.clone());
+            }
+            materials.insert(name, description.build(&entry, &textures)?);
+        }
+
+        let mut world = HittableList::new();
+        let mut lights = HittableList::new();
+        let mut named: NamedObjects = BTreeMap::new();
+        for (index, description) in self.objects.into_iter().enumerate() {
+            let is_light = description.is_sampleable_light(&light_materials, &named);
+            let name = description.name().map(String::from);
+            let object = description.build(index, &materials, &named, base_dir, None)?;
+            if is_light {
+                lights.add(object.clone());
+            }
+            if let Some(name) = name {
+                named.insert(name, (object.clone(), is_light));
+            }
+            world.add(object);
+        }
+
+        Ok(Scene {
+            camera,
+            world,
+            lights,
+            tone_mapper,
+        })
+    }
+}
+
+impl ToneMappingDescription {
+    fn build(self) -> Result<ToneMapper, SceneError> {
+        let invalid = |message: &str| SceneError::Invalid {
+            entry: String::from("tone_mapping"),
+            message: message.to_string(),
+        };
+
+        let operator = match self.operator.unwrap_or(OperatorName::Clamp) {
+            OperatorName::Clamp => ToneMapOperator::Clamp,
+            OperatorName::Reinhard => ToneMapOperator::Reinhard,
+            OperatorName::ReinhardExtended => {
+                let white_point = self.white_point.unwrap_or(4.0);
+                if white_point <= 0.0 {
+                    return Err(invalid("white_point must be positive"));
+                }
+                ToneMapOperator::ReinhardExtended { white_point }
+            }
+            OperatorName::Aces => ToneMapOperator::Aces,
+            OperatorName::Hable => ToneMapOperator::Hable,
+        };
+        if self.white_point.is_some()
+            && !matches!(operator, ToneMapOperator::ReinhardExtended { .. })
+        {
+            return Err(invalid("white_point only applies to reinhard_extended"));
+        }
+
+        Ok(ToneMapper::new(self.exposure.unwrap_or(0.0), operator))
+    }
+}
+
+impl CameraDescription {
+    fn build(self) -> Result<Camera, SceneError> {
+        let invalid = |message: &str| SceneError::Invalid {
+            entry: String::from("camera"),
+            message: message.to_string(),
+        };
+
+        let mut cam = Camera::new();
+        if let Some(aspect_ratio) = self.aspect_ratio {
+            if aspect_ratio <= 0.0 {
+                return Err(invalid("aspect_ratio must be positive"));
+            }
+            cam.aspect_ratio = aspect_ratio;
+        }
+        if let Some(image_width) = self.image_width {
+            if image_width == 0 {
+                return Err(invalid("image_width must be at least 1"));
+            }
+            cam.image_width = image_width;
+        }
+        if let Some(samples_per_pixel) = self.samples_per_pixel {
+            if samples_per_pixel == 0 {
+                return Err(invalid("samples_per_pixel must be at least 1"));
+            }
+            cam.samples_per_pixel = samples_per_pixel;
+        }
+        if let Some(fvov) = self.fvov {
+            if fvov <= 0.0 || fvov >= 180.0 {
+                return Err(invalid("fvov must be between 0 and 180 degrees"));
+            }
+            cam.fvov = fvov;
+        }
+        if let Some(defocus_angle) = self.defocus_angle {
+            if !(0.0..180.0).contains(&defocus_angle) {
+                return Err(invalid("defocus_angle must be between 0 and 180 degrees"));
+            }
+            cam.defocus_angle = defocus_angle;
+        }
+        if let Some(focus_dist) = self.focus_dist {
+            if focus_dist <= 0.0 {
+                return Err(invalid("focus_dist must be positive"));
+            }
+            cam.focus_dist = focus_dist;
+        }
+        cam.shutter_open = self.shutter_open.unwrap_or(cam.shutter_open);
+        cam.shutter_close = self.shutter_close.unwrap_or(cam.shutter_close);
+        if cam.shutter_close < cam.shutter_open {
+            return Err(invalid("shutter_close must not be before shutter_open"));
+        }
+        cam.max_depth = self.max_depth.unwrap_or(cam.max_depth);
+        cam.roulette_depth = self.roulette_depth.unwrap_or(cam.roulette_depth);
+        cam.lookfrom = self.lookfrom.map(to_vec3).unwrap_or(cam.lookfrom);
+        cam.lookat = self.lookat.map(to_vec3).unwrap_or(cam.lookat);
+        cam.vup = self.vup.map(to_vec3).unwrap_or(cam.vup);
+        cam.thread_count = self.thread_count.unwrap_or(cam.thread_count);
+        cam.seed = self.seed.or(cam.seed);
+        if let Some(sampler) = self.sampler {
+            cam.sampler = match sampler {
+                SamplerName::Independent => SamplerType::Independent,
+                SamplerName::Stratified => SamplerType::Stratified,
+                SamplerName::Halton => SamplerType::Halton,
+                SamplerName::Sobol => SamplerType::Sobol,
+            };
+        }
+        if let Some(background) = self.background {
+            cam.background = match background {
+                BackgroundDescription::None => Background::None,
+                BackgroundDescription::Solid { color } => Background::Solid(to_vec3(color)),
+                BackgroundDescription::Gradient { bottom, top } => Background::Gradient {
+                    bottom: to_vec3(bottom),
+                    top: to_vec3(top),
+                },
+            };
+        }
+
+        if let Some(adaptive) = self.adaptive {
+            let defaults = AdaptiveSampling::default();
+            let settings = AdaptiveSampling {
+                threshold: adaptive.threshold.unwrap_or(defaults.threshold),
+                min_samples: adaptive.min_samples.unwrap_or(defaults.min_samples),
+                max_samples: adaptive.max_samples.unwrap_or(defaults.max_samples),
+            };
+            if settings.threshold <= 0.0 {
+                return Err(invalid("adaptive threshold must be positive"));
+            }
+            if settings.min_samples == 0 {
+                return Err(invalid("adaptive min_samples must be at least 1"));
+            }
+            if settings.max_samples < settings.min_samples {
+                return Err(invalid(
+                    "adaptive max_samples must not be below min_samples",
+                ));
+            }
+            cam.adaptive = Some(settings);
+        }
+
+        if (cam.lookfrom - cam.lookat).near_zero() {
+            return Err(invalid("lookfrom and lookat must differ"));
+        }
+        Ok(cam)
+    }
+}
+
+impl TextureDescription {
+    fn build(self, entry: &str, base_dir: &Path) -> Result<Arc<dyn Texture>, SceneError> {
+        let invalid = |message: String| SceneError::Invalid {
+            entry: entry.to_string(),
+            message,
+        };
+
+        Ok(match self {
+            TextureDescription::Solid { color } => Arc::new(SolidColor::new(to_vec3(color))),
+            TextureDescription::Checker { scale, even, odd } => {
+                if scale <= 0.0 {
+                    return Err(invalid(String::from("scale must be positive")));
+                }
+                Arc::new(CheckerTexture::from_colors(
+                    scale,
+                    to_vec3(even),
+                    to_vec3(odd),
+                ))
+            }
+            TextureDescription::Image { path } => {
+                let path = base_dir.join(path);
+                let tex = ImageTexture::load(&path)
+                    .map_err(|e| invalid(format!("{}: {}", path.display(), e)))?;
+                Arc::new(tex)
+            }
+            TextureDescription::Noise { scale } => Arc::new(NoiseTexture::new(scale)),
+        })
+    }
+}
+
+impl ColorOrTexture {
+    fn build(self, entry: &str, textures: &Textures) -> Result<Arc<dyn Texture>, SceneError> {
+        match self {
+            ColorOrTexture::Color(color) => Ok(Arc::new(SolidColor::new(to_vec3(color)))),
+            ColorOrTexture::Texture(name) => match textures.get(&name) {
+                Some(tex) => Ok(tex.clone()),
+                None => Err(SceneError::Invalid {
+                    entry: entry.to_string(),
+                    message: format!("unknown texture '{}'", name),
+                }),
+            },
+        }
+    }
+}
+
+impl ScalarOrTexture {
+    fn build(self, entry: &str, textures: &Textures) -> Result<Arc<dyn Texture>, SceneError> {
+        match self {
+            ScalarOrTexture::Value(value) => {
+                if !(0.0..=1.0).contains(&value) {
+                    return Err(SceneError::Invalid {
+                        entry: entry.to_string(),
+                        message: "principled parameters must be between 0 and 1".to_string(),
+                    });
+                }
+                Ok(scalar(value))
+            }
+            ScalarOrTexture::Texture(name) => ColorOrTexture::Texture(name).build(entry, textures),
+        }
+    }
+}
+
+impl MaterialDescription {
+    fn build(self, entry: &str, textures: &Textures) -> Result<Arc<dyn Material>, SceneError> {
+        let invalid = |message: &str| SceneError::Invalid {
+            entry: entry.to_string(),
+            message: message.to_string(),
+        };
+
+        Ok(match self {
+            MaterialDescription::Lambertian { albedo } => {
+                Arc::new(Lambertian::from_texture(albedo.build(entry, textures)?))
+            }
+            MaterialDescription::Metal { albedo, fuzz } => {
+                if fuzz < 0.0 {
+                    return Err(invalid("fuzz must not be negative"));
+                }
+                Arc::new(Metal::from_texture(albedo.build(entry, textures)?, fuzz))
+            }
+            MaterialDescription::Conductor {
+                preset,
+                eta,
+                k,
+                roughness,
+                roughness_u,
+                roughness_v,
+            } => {
+                let (eta, k) = match (preset, eta, k) {
+                    (Some(preset), None, None) => {
+                        let preset = match preset {
+                            ConductorName::Gold => ConductorPreset::Gold,
+                            ConductorName::Copper => ConductorPreset::Copper,
+                            ConductorName::Aluminum => ConductorPreset::Aluminum,
+                            ConductorName::Silver => ConductorPreset::Silver,
+                        };
+                        preset.ior()
+                    }
+                    (None, Some(eta), Some(k)) => {
+                        if eta.iter().chain(&k).any(|&c| c < 0.0) {
+                            return Err(invalid("eta and k must not be negative"));
+                        }
+                        (to_vec3(eta), to_vec3(k))
+                    }
+                    _ => return Err(invalid("give either a preset or both eta and k")),
+                };
+
+                let roughness = roughness.unwrap_or(0.0);
+                let roughness_u = roughness_u.unwrap_or(roughness);
+                let roughness_v = roughness_v.unwrap_or(roughness);
+                if [roughness_u, roughness_v]
+                    .iter()
+                    .any(|r| !(0.0..=1.0).contains(r))
+                {
+                    return Err(invalid("roughness must be between 0 and 1"));
+                }
+                Arc::new(Conductor::anisotropic(eta, k, roughness_u, roughness_v))
+            }
+            MaterialDescription::Dielectric {
+                refraction_index,
+                roughness,
+                absorption_color,
+                absorption_distance,
+                exact_fresnel,
+            } => {
+                if refraction_index <= 0.0 {
+                    return Err(invalid("refraction_index must be positive"));
+                }
+                let mut dielectric = Dielectric::new(refraction_index);
+                if let Some(roughness) = roughness {
+                    if !(0.0..=1.0).contains(&roughness) {
+                        return Err(invalid("roughness must be between 0 and 1"));
+                    }
+                    dielectric = dielectric.with_roughness(roughness);
+                }
+                match (absorption_color, absorption_distance) {
+                    (Some(color), distance) => {
+                        let distance = distance.unwrap_or(1.0);
+                        if color.iter().any(|c| !(0.0..=1.0).contains(c)) || distance <= 0.0 {
+                            return Err(invalid(
+                                "absorption_color must be in [0, 1] and absorption_distance positive",
+                            ));
+                        }
+                        dielectric = dielectric.with_absorption(to_vec3(color), distance);
+                    }
+                    (None, Some(_)) => {
+                        return Err(invalid("absorption_distance needs an absorption_color"))
+                    }
+                    (None, None) => {}
+                }
+                if exact_fresnel.unwrap_or(false) {
+                    dielectric = dielectric.with_exact_fresnel();
+                }
+                Arc::new(dielectric)
+            }
+            MaterialDescription::Principled {
+                base_color,
+                metallic,
+                roughness,
+                specular,
+                sheen,
+                clearcoat,
+                clearcoat_roughness,
+                transmission,
+                refraction_index,
+            } => {
+                let mut mat = Principled::from_texture(base_color.build(entry, textures)?);
+                let param = |param: Option<ScalarOrTexture>| -> Result<_, SceneError> {
+                    param.map(|p| p.build(entry, textures)).transpose()
+                };
+                if let Some(tex) = param(metallic)? {
+                    mat = mat.with_metallic(tex);
+                }
+                if let Some(tex) = param(roughness)? {
+                    mat = mat.with_roughness(tex);
+                }
+                if let Some(tex) = param(specular)? {
+                    mat = mat.with_specular(tex);
+                }
+                if let Some(tex) = param(sheen)? {
+                    mat = mat.with_sheen(tex);
+                }
+                if let Some(tex) = param(clearcoat)? {
+                    mat = mat.with_clearcoat(tex);
+                }
+                if let Some(tex) = param(clearcoat_roughness)? {
+                    mat = mat.with_clearcoat_roughness(tex);
+                }
+                match (param(transmission)?, refraction_index) {
+                    (Some(tex), refraction_index) => {
+                        let refraction_index = refraction_index.unwrap_or(1.5);
+                        if refraction_index <= 0.0 {
+                            return Err(invalid("refraction_index must be positive"));
+                        }
+                        mat = mat.with_transmission(tex, refraction_index);
+                    }
+                    (None, Some(_)) => {
+                        return Err(invalid("refraction_index needs a transmission"))
+                    }
+                    (None, None) => {}
+                }
+                Arc::new(mat)
+            }
+            MaterialDescription::DiffuseLight { emit } => {
+                if let ColorOrTexture::Color(color) = &emit {
+                    if color.iter().any(|&c| c < 0.0) {
+                        return Err(invalid("emit must not be negative"));
+                    }
+                }
+                Arc::new(DiffuseLight::from_texture(emit.build(entry, textures)?))
+            }
+            MaterialDescription::Isotropic { albedo } => {
+                Arc::new(Isotropic::from_texture(albedo.build(entry, textures)?))
+            }
+            MaterialDescription::HenyeyGreenstein { albedo, g } => {
+                if g <= -1.0 || g >= 1.0 {
+                    return Err(invalid("g must be between -1 and 1"));
+                }
+                Arc::new(HenyeyGreenstein::from_texture(
+                    albedo.build(entry, textures)?,
+                    g,
+                ))
+            }
+        })
+    }
+}
+
+impl ObjectDescription {
+    // Surfaces that name no material get default_material, when there is one
+    fn build(
+        self,
+        index: usize,
+        materials: &BTreeMap<String, Arc<dyn Material>>,
+        named: &NamedObjects,
+        base_dir: &Path,
+        default_material: Option<Arc<dyn Material>>,
+    ) -> Result<Arc<dyn Hittable>, SceneError> {
+        let entry = match self.name() {
+            Some(name) => format!("objects[{}] '{}'", index, name),
+            None => format!("objects[{}]", index),
+        };
+        let lookup = |name: &str| match materials.get(name) {
+            Some(mat) => Ok(mat.clone()),
+            None => Err(SceneError::Invalid {
+                entry: entry.clone(),
+                message: format!("unknown material '{}'", name),
+            }),
+        };
+        let surface = |material: Option<String>| match (material, &default_material) {
+            (Some(name), _) => lookup(&name),
+            (None, Some(mat)) => Ok(mat.clone()),
+            (None, None) => Err(SceneError::Invalid {
+                entry: entry.clone(),
+                message: String::from("missing material"),
+            }),
+        };
+
+        Ok(match self {
+            ObjectDescription::Sphere {
+                center,
+                center2,
+                radius,
+                material,
+                ..
+            } => {
+                if radius <= 0.0 {
+                    return Err(SceneError::Invalid {
+                        entry,
+                        message: String::from("radius must be positive"),
+                    });
+                }
+                let center2 = center2.unwrap_or(center);
+                Arc::new(Sphere::new_moving(
+                    to_vec3(center),
+                    to_vec3(center2),
+                    radius,
+                    surface(material)?,
+                ))
+            }
+            ObjectDescription::Triangle {
+                vertices, material, ..
+            } => Arc::new(Triangle::new(
+                to_vec3(vertices[0]),
+                to_vec3(vertices[1]),
+                to_vec3(vertices[2]),
+                surface(material)?,
+            )),
+            ObjectDescription::Quad {
+                corner,
+                u,
+                v,
+                material,
+                ..
+            } => {
+                let (u, v) = (to_vec3(u), to_vec3(v));
+                if cross(u, v).near_zero() {
+                    return Err(SceneError::Invalid {
+                        entry,
+                        message: String::from("quad edges must not be parallel"),
+                    });
+                }
+                Arc::new(Quad::new(to_vec3(corner), u, v, surface(material)?))
+            }
+            ObjectDescription::Box {
+                min, max, material, ..
+            } => {
+                let (a, b) = (to_vec3(min), to_vec3(max));
+                if (0..3).any(|i| a[i] == b[i]) {
+                    return Err(SceneError::Invalid {
+                        entry,
+                        message: String::from("box must not be flat"),
+                    });
+                }
+                Arc::new(make_box(a, b, surface(material)?))
+            }
+            ObjectDescription::Plane {
+                point,
+                normal,
+                material,
+                ..
+            } => {
+                let normal = to_vec3(normal);
+                if normal.near_zero() {
+                    return Err(SceneError::Invalid {
+                        entry,
+                        message: String::from("normal must not be zero"),
+                    });
+                }
+                Arc::new(Plane::new(to_vec3(point), normal, surface(material)?))
+            }
+            ObjectDescription::Disk {
+                center,
+                normal,
+                radius,
+                material,
+                ..
+            } => {
+                let normal = to_vec3(normal);
+                if normal.near_zero() || radius <= 0.0 {
+                    return Err(SceneError::Invalid {
+                        entry,
+                        message: String::from("disk needs a nonzero normal and a positive radius"),
+                    });
+                }
+                Arc::new(Disk::new(
+                    to_vec3(center),
+                    normal,
+                    radius,
+                    surface(material)?,
+                ))
+            }
+            ObjectDescription::Obj { path, .. } => {
+                let list = load_obj(base_dir.join(path))
+                    .map_err(|source| SceneError::Obj { entry, source })?;
+                Arc::new(list)
+            }
+            ObjectDescription::ConstantMedium {
+                boundary,
+                density,
+                material,
+                ..
+            } => {
+                if !(density.is_finite() && density > 0.0) {
+                    return Err(SceneError::Invalid {
+                        entry,
+                        message: String::from("density must be positive"),
+                    });
+                }
+                let phase_function = lookup(&material)?;
+                let boundary = boundary.build(
+                    index,
+                    materials,
+                    named,
+                    base_dir,
+                    Some(Arc::new(DefaultMaterial::new())),
+                )?;
+                Arc::new(ConstantMedium::from_material(
+                    boundary,
+                    density,
+                    phase_function,
+                ))
+            }
+            ObjectDescription::GridMedium {
+                path,
+                resolution,
+                min,
+                max,
+                density,
+                material,
+                ..
+            } => {
+                let invalid = |message: &str| SceneError::Invalid {
+                    entry: entry.clone(),
+                    message: message.to_string(),
+                };
+                if !(density.is_finite() && density > 0.0) {
+                    return Err(invalid("density must be positive"));
+                }
+                let phase_function = lookup(&material)?;
+
+                let path = base_dir.join(path);
+                let is_vol = path.extension().is_some_and(|ext| ext == "vol");
+                let grid = match (is_vol, resolution) {
+                    (true, None) => load_vol(&path),
+                    (false, Some(resolution)) => load_raw(&path, resolution),
+                    (true, Some(_)) => {
+                        return Err(invalid(".vol files carry their own resolution"))
+                    }
+                    (false, None) => return Err(invalid("raw grids need a resolution")),
+                }
+                .map_err(|source| SceneError::Volume {
+                    entry: entry.clone(),
+                    source,
+                })?;
+
+                let grid = match (min, max) {
+                    (Some(min), Some(max)) => {
+                        if (0..3).any(|i| min[i] >= max[i]) {
+                            return Err(invalid("min must be below max on every axis"));
+                        }
+                        grid.with_bounds(to_vec3(min), to_vec3(max))
+                    }
+                    (None, None) => grid,
+                    _ => return Err(invalid("min and max must be given together")),
+                };
+                Arc::new(GridMedium::new(Arc::new(grid), density, phase_function))
+            }
+            ObjectDescription::Instance {
+                object, transforms, ..
+            } => {
+                let invalid = |message: String| SceneError::Invalid {
+                    entry: entry.clone(),
+                    message,
+                };
+                let Some((object, _)) = named.get(&object) else {
+                    return Err(invalid(format!("unknown object '{}'", object)));
+                };
+
+                let mut to_world = Mat4::identity();
+                for transform in transforms {
+                    to_world = transform.build().map_err(invalid)? * to_world;
+                }
+                match Instance::new(object.clone(), to_world) {
+                    Some(instance) => Arc::new(instance),
+                    None => return Err(invalid(String::from("transform is not invertible"))),
+                }
+            }
+        })
+    }
+
+    fn name(&self) -> Option<&str> {
+        match self {
+            ObjectDescription::Sphere { name, .. }
+            | ObjectDescription::Triangle { name, .. }
+            | ObjectDescription::Quad { name, .. }
+            | ObjectDescription::Box { name, .. }
+            | ObjectDescription::Plane { name, .. }
+            | ObjectDescription::Disk { name, .. }
+            | ObjectDescription::Obj { name, .. }
+            | ObjectDescription::ConstantMedium { name, .. }
+            | ObjectDescription::GridMedium { name, .. }
+            | ObjectDescription::Instance { name, .. } => name.as_deref(),
+        }
+    }
+
+    // Emissive shapes that support direct light sampling
+    fn is_sampleable_light(
+        &self,
+        light_materials: &BTreeSet<String>,
+        named: &NamedObjects,
+    ) -> bool {
+        match self {
+            ObjectDescription::Sphere { material, .. }
+            | ObjectDescription::Quad { material, .. }
+            | ObjectDescription::Box { material, .. }
+            | ObjectDescription::Disk { material, .. } => material
+                .as_ref()
+                .is_some_and(|material| light_materials.contains(material)),
+            ObjectDescription::Instance { object, .. } => {
+                named.get(object).is_some_and(|(_, is_light)| *is_light)
+            }
+            ObjectDescription::Triangle { .. }
+            | ObjectDescription::Plane { .. }
+            | ObjectDescription::Obj { .. }
+            | ObjectDescription::ConstantMedium { .. }
+            | ObjectDescription::GridMedium { .. } => false,
+        }
+    }
+}
+
+impl TransformDescription {
+    fn build(self) -> Result<Mat4, String> {
+        Ok(match self {
+            TransformDescription::Translate { offset } => Mat4::translate(to_vec3(offset)),
+            TransformDescription::Rotate { axis, degrees } => {
+                if to_vec3(axis).near_zero() {
+                    return Err(String::from("rotation axis must not be zero"));
+                }
+                Mat4::rotate(to_vec3(axis), degrees)
+            }
+            TransformDescription::Scale { factors } => Mat4::scale(to_vec3(factors)),
+            TransformDescription::LookAt { from, target, up } => {
+                let (from, target, up) = (to_vec3(from), to_vec3(target), to_vec3(up));
+                if (target - from).near_zero() || cross(up, target - from).near_zero() {
+                    return Err(String::from(
+                        "look_at needs distinct points and a tilted up",
+                    ));
+                }
+                Mat4::look_at(from, target, up)
+            }
+        })
+    }
+}
+
+fn to_vec3(v: [f64; 3]) -> Vec3 {
+    Point3::new(v[0], v[1], v[2])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SCENE: &str = r#"
+[camera]
+image_width = 40
+samples_per_pixel = 4
+lookfrom = [0, 0, 1]
+lookat = [0, 0, -1]
+
+[materials.ground]
+type = "lambertian"
+albedo = [0.8, 0.8, 0.0]
+
+[materials.glass]
+type = "dielectric"
+refraction_index = 1.5
+
+[[objects]]
+type = "sphere"
+center = [0, -100.5, -1]
+radius = 100
+material = "ground"
+
+[[objects]]
+type = "sphere"
+name = "marble"
+center = [0, 0, -1]
+radius = 0.5
+material = "glass"
+"#;
+
+    #[test]
+    fn test_parse_scene() {
+        let scene = parse_scene(SCENE, Path::new("scene.toml")).unwrap();
+        assert_eq!(scene.camera.image_width, 40);
+        assert_eq!(scene.camera.samples_per_pixel, 4);
+        assert_eq!(scene.camera.lookfrom, Point3::new(0.0, 0.0, 1.0));
+        assert_eq!(scene.world.objects().len(), 2);
+    }
+
+    #[test]
+    fn test_parse_sampler() {
+        let source = SCENE.replace("[camera]\n", "[camera]\nsampler = \"sobol\"\n");
+        let scene = parse_scene(&source, Path::new("scene.toml")).unwrap();
+        assert_eq!(scene.camera.sampler, SamplerType::Sobol);
+
+        let source = source.replace("sobol", "random");
+        let err = parse_scene(&source, Path::new("scene.toml")).err().unwrap();
+        assert!(matches!(err, SceneError::Parse { .. }));
+    }
+
+    #[test]
+    fn test_parse_adaptive_sampling() {
+        let source = format!(
+            "{}\n[camera.adaptive]\nthreshold = 0.05\nmax_samples = 256\n",
+            SCENE
+        );
+        let scene = parse_scene(&source, Path::new("scene.toml")).unwrap();
+        assert_eq!(
+            scene.camera.adaptive,
+            Some(AdaptiveSampling {
+                threshold: 0.05,
+                min_samples: 16,
+                max_samples: 256,
+            })
+        );
+
+        let source = source.replace("max_samples = 256", "max_samples = 8");
+        let err = parse_scene(&source, Path::new("scene.toml")).err().unwrap();
+        assert_eq!(
+            err.to_string(),
+            "camera: adaptive max_samples must not be below min_samples"
+        );
+    }
+
+    #[test]
+    fn test_emissive_spheres_and_quads_are_lights() {
+        let source = format!(
+            "{}{}",
+            SCENE,
+            "\n[materials.lamp]\ntype = \"diffuse_light\"\nemit = [4, 4, 4]\n\n\
+             [[objects]]\ntype = \"quad\"\ncorner = [0, 2, 0]\nu = [1, 0, 0]\nv = [0, 0, 1]\n\
+             material = \"lamp\"\n"
+        );
+        let scene = parse_scene(&source, Path::new("scene.toml")).unwrap();
+        assert_eq!(scene.world.objects().len(), 3);
+        assert_eq!(scene.lights.objects().len(), 1);
+
+        let source = source.replace("v = [0, 0, 1]", "v = [2, 0, 0]");
+        let err = parse_scene(&source, Path::new("scene.toml")).err().unwrap();
+        assert_eq!(
+            err.to_string(),
+            "objects[2]: quad edges must not be parallel"
+        );
+    }
+
+    #[test]
+    fn test_parse_planes_boxes_and_disks() {
+        let source = format!(
+            "{}{}",
+            SCENE,
+            "\n[[objects]]\ntype = \"plane\"\npoint = [0, -1, 0]\nnormal = [0, 1, 0]\n\
+             material = \"ground\"\n\n\
+             [[objects]]\ntype = \"box\"\nmin = [0, 0, 0]\nmax = [1, 2, 3]\nmaterial = \"ground\"\n\n\
+             [[objects]]\ntype = \"disk\"\ncenter = [0, 3, 0]\nnormal = [0, -1, 0]\nradius = 0.5\n\
+             material = \"ground\"\n"
+        );
+        let scene = parse_scene(&source, Path::new("scene.toml")).unwrap();
+        assert_eq!(scene.world.objects().len(), 5);
+
+        let source = source.replace("max = [1, 2, 3]", "max = [1, 0, 3]");
+        let err = parse_scene(&source, Path::new("scene.toml")).err().unwrap();
+        assert_eq!(err.to_string(), "objects[3]: box must not be flat");
+    }
+
+    #[test]
+    fn test_parse_instances() {
+        let instance = "\n[[objects]]\ntype = \"instance\"\nobject = \"marble\"\n\
+                        transforms = [{ type = \"scale\", factors = [1, 2, 1] }, \
+                        { type = \"translate\", offset = [2, 0, 0] }]\n";
+        let scene =
+            parse_scene(&format!("{}{}", SCENE, instance), Path::new("scene.toml")).unwrap();
+        assert_eq!(scene.world.objects().len(), 3);
+        let bbox = scene.world.objects()[2].bounding_box();
+        assert!((bbox.x.min - 1.5).abs() < 1e-9 && (bbox.y.max - 1.0).abs() < 1e-9);
+
+        let source = format!("{}{}", SCENE, instance.replace("marble", "teapot"));
+        let err = parse_scene(&source, Path::new("scene.toml")).err().unwrap();
+        assert_eq!(err.to_string(), "objects[2]: unknown object 'teapot'");
+
+        let source = format!("{}{}", SCENE, instance.replace("[1, 2, 1]", "[1, 0, 1]"));
+        let err = parse_scene(&source, Path::new("scene.toml")).err().unwrap();
+        assert_eq!(err.to_string(), "objects[2]: transform is not invertible");
+    }
+
+    #[test]
+    fn test_parse_constant_medium() {
+        let medium = "\n[materials.fog]\ntype = \"isotropic\"\nalbedo = [0.9, 0.9, 0.9]\n\n\
+                      [[objects]]\ntype = \"constant_medium\"\ndensity = 0.2\nmaterial = \"fog\"\n\
+                      boundary = { type = \"box\", min = [-1, -1, -1], max = [1, 1, 1] }\n";
+        let scene = parse_scene(&format!("{}{}", SCENE, medium), Path::new("scene.toml")).unwrap();
+        assert_eq!(scene.world.objects().len(), 3);
+
+        // A boundary may still name a material, which must exist
+        let source = format!(
+            "{}{}",
+            SCENE,
+            medium.replace(" }", ", material = \"mist\" }")
+        );
+        let err = parse_scene(&source, Path::new("scene.toml")).err().unwrap();
+        assert_eq!(err.to_string(), "objects[2]: unknown material 'mist'");
+
+        for density in ["0", "nan", "inf"] {
+            let source = format!("{}{}", SCENE, medium.replace("0.2", density));
+            let err = parse_scene(&source, Path::new("scene.toml")).err().unwrap();
+            assert_eq!(err.to_string(), "objects[2]: density must be positive");
+        }
+    }
+
+    #[test]
+    fn test_parse_grid_medium() {
+        let dir = std::env::temp_dir().join("pathtracer_scene_grid_test");
+        fs::create_dir_all(&dir).unwrap();
+        let raw: Vec<u8> = [0.0f32, 1.0].iter().flat_map(|v| v.to_le_bytes()).collect();
+        fs::write(dir.join("smoke.raw"), raw).unwrap();
+
+        let medium =
+            "\n[materials.smoke]\ntype = \"henyey_greenstein\"\nalbedo = [0.8, 0.8, 0.8]\n\
+                      g = 0.6\n\n[[objects]]\ntype = \"grid_medium\"\npath = \"smoke.raw\"\n\
+                      resolution = [2, 1, 1]\nmin = [-1, -1, -1]\nmax = [1, 1, 1]\n\
+                      density = 3.0\nmaterial = \"smoke\"\n";
+        let source = format!("{}{}", SCENE, medium);
+        let scene = parse_scene(&source, &dir.join("scene.toml")).unwrap();
+        assert_eq!(scene.world.objects().len(), 3);
+        assert!(scene.world.has_media());
+
+        for density in ["0.0", "-3.0", "nan"] {
+            let source = source.replace("density = 3.0", &format!("density = {density}"));
+            let err = parse_scene(&source, &dir.join("scene.toml")).err().unwrap();
+            assert_eq!(err.to_string(), "objects[2]: density must be positive");
+        }
+
+        let source = source.replace("resolution = [2, 1, 1]\n", "");
+        let err = parse_scene(&source, &dir.join("scene.toml")).err().unwrap();
+        assert_eq!(err.to_string(), "objects[2]: raw grids need a resolution");
+
+        let source = format!("{}{}", SCENE, medium.replace("[2, 1, 1]", "[2, 2, 1]"));
+        let err = parse_scene(&source, &dir.join("scene.toml")).err().unwrap();
+        assert!(matches!(err, SceneError::Volume { .. }));
+    }
+
+    #[test]
+    fn test_parse_conductor() {
+        let source = SCENE.replace(
+            "type = \"dielectric\"\nrefraction_index = 1.5",
+            "type = \"conductor\"\npreset = \"gold\"\nroughness = 0.2\nroughness_v = 0.5",
+        );
+        assert!(parse_scene(&source, Path::new("scene.toml")).is_ok());
+
+        let source = SCENE.replace(
+            "type = \"dielectric\"\nrefraction_index = 1.5",
+            "type = \"conductor\"\npreset = \"gold\"\neta = [1, 1, 1]",
+        );
+        let err = parse_scene(&source, Path::new("scene.toml")).err().unwrap();
+        assert_eq!(
+            err.to_string(),
+            "materials.glass: give either a preset or both eta and k"
+        );
+    }
+
+    #[test]
+    fn test_parse_rough_absorbing_dielectric() {
+        let source = SCENE.replace(
+            "refraction_index = 1.5",
+            "refraction_index = 1.5\nroughness = 0.2\nabsorption_color = [0.9, 0.5, 0.5]\n\
+             absorption_distance = 0.5\nexact_fresnel = true",
+        );
+        assert!(parse_scene(&source, Path::new("scene.toml")).is_ok());
+
+        let source = SCENE.replace(
+            "refraction_index = 1.5",
+            "refraction_index = 1.5\nabsorption_distance = 0.5",
+        );
+        let err = parse_scene(&source, Path::new("scene.toml")).err().unwrap();
+        assert_eq!(
+            err.to_string(),
+            "materials.glass: absorption_distance needs an absorption_color"
+        );
+    }
+
+    #[test]
+    fn test_parse_principled() {
+        let source = SCENE.replace(
+            "[materials.ground]\ntype = \"lambertian\"\nalbedo = [0.8, 0.8, 0.0]",
+            "[textures.checker]\ntype = \"checker\"\nscale = 0.3\neven = [0, 0, 0]\nodd = [1, 1, 1]\n\n\
+             [materials.ground]\ntype = \"principled\"\nbase_color = [0.8, 0.8, 0.0]\n\
+             metallic = \"checker\"\nroughness = 0.4\nclearcoat = 1\ntransmission = 0.2\n\
+             refraction_index = 1.33",
+        );
+        assert!(parse_scene(&source, Path::new("scene.toml")).is_ok());
+
+        let source = source.replace("roughness = 0.4", "roughness = 1.5");
+        let err = parse_scene(&source, Path::new("scene.toml")).err().unwrap();
+        assert_eq!(
+            err.to_string(),
+            "materials.ground: principled parameters must be between 0 and 1"
+        );
+    }
+
+    #[test]
+    fn test_parse_light_and_background() {
+        let source = format!(
+            "{}{}",
+            SCENE.replace(
+                "type = \"dielectric\"\nrefraction_index = 1.5",
+                "type = \"diffuse_light\"\nemit = [4, 4, 4]"
+            ),
+            "\n[camera.background]\ntype = \"solid\"\ncolor = [0.1, 0.1, 0.1]\n"
+        );
+        let scene = parse_scene(&source, Path::new("scene.toml")).unwrap();
+        assert_eq!(
+            scene.camera.background,
+            Background::Solid(Vec3::new(0.1, 0.1, 0.1))
+        );
+    }
+
+    #[test]
+    fn test_material_texture_reference() {
+        let source = SCENE.replace(
+            "[materials.ground]\ntype = \"lambertian\"\nalbedo = [0.8, 0.8, 0.0]",
+            "[textures.checker]\ntype = \"checker\"\nscale = 0.3\neven = [0, 0, 0]\nodd = [1, 1, 1]\n\n\
+             [materials.ground]\ntype = \"lambertian\"\nalbedo = \"checker\"",
+        );
+        assert!(parse_scene(&source, Path::new("scene.toml")).is_ok());
+
+        let source = source.replace("albedo = \"checker\"", "albedo = \"marble\"");
+        let err = parse_scene(&source, Path::new("scene.toml")).err().unwrap();
+        assert_eq!(
+            err.to_string(),
+            "materials.ground: unknown texture 'marble'"
+        );
+    }
+
+    #[test]
+    fn test_parse_tone_mapping() {
+        let source = format!(
+            "{}\n[tone_mapping]\nexposure = -1.0\noperator = \"reinhard_extended\"\nwhite_point = 8.0\n",
+            SCENE
+        );
+        let scene = parse_scene(&source, Path::new("scene.toml")).unwrap();
+        assert_eq!(
+            scene.tone_mapper,
+            ToneMapper::new(-1.0, ToneMapOperator::ReinhardExtended { white_point: 8.0 })
+        );
+
+        let source = source.replace("reinhard_extended", "aces");
+        let err = parse_scene(&source, Path::new("scene.toml")).err().unwrap();
+        assert_eq!(
+            err.to_string(),
+            "tone_mapping: white_point only applies to reinhard_extended"
+        );
+    }
+
+    #[test]
+    fn test_unknown_material_names_entry() {
+        let source = SCENE.replace("material = \"glass\"", "material = \"steel\"");
+        let err = parse_scene(&source, Path::new("scene.toml")).err().unwrap();
+        assert_eq!(
+            err.to_string(),
+            "objects[1] 'marble': unknown material 'steel'"
+        );
+    }
+
+    #[test]
+    fn test_surfaces_need_a_material() {
+        let source = SCENE.replace("material = \"glass\"\n", "");
+        let err = parse_scene(&source, Path::new("scene.toml")).err().unwrap();
+        assert_eq!(err.to_string(), "objects[1] 'marble': missing material");
+    }
+
+    #[test]
+    fn test_invalid_values_name_entry() {
+        let source = SCENE.replace("refraction_index = 1.5", "refraction_index = -1.0");
+        let err = parse_scene(&source, Path::new("scene.toml")).err().unwrap();
+        assert_eq!(
+            err.to_string(),
+            "materials.glass: refraction_index must be positive"
+        );
+
+        let source = SCENE.replace("image_width = 40", "image_width = 0");
+        let err = parse_scene(&source, Path::new("scene.toml")).err().unwrap();
+        assert_eq!(err.to_string(), "camera: image_width must be at least 1");
+    }
+
+    #[test]
+    fn test_unknown_object_type_is_a_parse_error() {
+        let source = SCENE.replace("type = \"sphere\"", "type = \"cube\"");
+        match parse_scene(&source, Path::new("scene.toml")) {
+            Err(SceneError::Parse { message, .. }) => assert!(message.contains("cube")),
+            _ => panic!("expected a parse error"),
+        }
+    }
+}
@@ -1,24 +1,76 @@
-use crate::{dot, HitRecord, Hittable, Interval, Material, Point3, Ray};
-use std::rc::Rc;
+use crate::{
+    dot, vec3::Onb, Aabb, HitRecord, Hittable, Interval, Material, Point3, Ray, Sampler,
+    ShapeSample, Vec3, INFINITY, PI,
+};
+use std::sync::Arc;
+
 pub struct Sphere {
-    center: Point3,
+    center: Ray, // Center at time 0, moving along the ray direction until time 1
     radius: f64,
-    mat: Rc<dyn Material>,
+    mat: Arc<dyn Material>,
+    bbox: Aabb,
 }
 
 impl Sphere {
-    pub fn new(center: Point3, radius: f64, mat: Rc<dyn Material>) -> Self {
+    // Stationary sphere
+    pub fn new(center: Point3, radius: f64, mat: Arc<dyn Material>) -> Self {
+        Self::new_moving(center, center, radius, mat)
+    }
+
+    // Sphere moving linearly from center1 at time 0 to center2 at time 1
+    pub fn new_moving(
+        center1: Point3,
+        center2: Point3,
+        radius: f64,
+        mat: Arc<dyn Material>,
+    ) -> Self {
+        let radius = f64::max(0.0, radius);
+        let rvec = Vec3::new(radius, radius, radius);
+        let box1 = Aabb::from_points(center1 - rvec, center1 + rvec);
+        let box2 = Aabb::from_points(center2 - rvec, center2 + rvec);
         Self {
-            center,
-            radius: f64::max(0.0, radius),
+            center: Ray::new(center1, center2 - center1),
+            radius,
             mat,
+            bbox: Aabb::surrounding(&box1, &box2),
+        }
+    }
+
+    fn get_sphere_uv(p: &Point3) -> (f64, f64) {
+        // p: a given point on the sphere of radius one, centered at the origin.
+        // u: returned value [0,1] of angle around the Y axis from X=-1.
+        // v: returned value [0,1] of angle from Y=-1 to Y=+1.
+        //     <1 0 0> yields <0.50 0.50>       <-1  0  0> yields <0.00 0.50>
+        //     <0 1 0> yields <0.50 1.00>       < 0 -1  0> yields <0.50 0.00>
+        //     <0 0 1> yields <0.25 0.50>       < 0  0 -1> yields <0.75 0.50>
+        let theta = f64::acos(-p.y());
+        let phi = f64::atan2(-p.z(), p.x()) + PI;
+
+        (phi / (2.0 * PI), theta / PI)
+    }
+
+    // Direction of increasing u at p on the unit sphere: around the Y axis, vanishing at the
+    // poles
+    fn tangent(p: &Point3) -> Vec3 {
+        Vec3::new(p.z(), 0.0, -p.x())
+    }
+
+    // Cosine of the half angle of the cone the sphere covers as seen from origin, or None when
+    // origin is inside the sphere
+    fn cone_cos_theta_max(&self, origin: &Point3, time: f64) -> Option<f64> {
+        let distance_squared = (self.center.at(time) - *origin).length_squared();
+        let radius_squared = self.radius * self.radius;
+        if distance_squared <= radius_squared {
+            return None;
         }
+        Some((1.0 - radius_squared / distance_squared).sqrt())
     }
 }
 
 impl Hittable for Sphere {
     fn hit_tmin_tmax(&self, r: &Ray, ray_tmin: f64, ray_tmax: f64, rec: &mut HitRecord) -> bool {
-        let oc = self.center - r.origin();
+        let current_center = self.center.at(r.time());
+        let oc = current_center - r.origin();
         let a = r.direction().length_squared();
         let h = dot(r.direction(), oc);
         let c = oc.length_squared() - self.radius * self.radius;
@@ -39,15 +91,18 @@ impl Hittable for Sphere {
 
         rec.t = root;
         rec.p = r.at(rec.t);
-        let outward_normal = (rec.p - self.center) / self.radius;
+        let outward_normal = (rec.p - current_center) / self.radius;
         rec.set_face_normal(r, &outward_normal);
+        (rec.u, rec.v) = Self::get_sphere_uv(&outward_normal);
+        rec.tangent = Self::tangent(&outward_normal);
         rec.mat = self.mat.clone();
 
-        return true;
+        true
     }
 
     fn hit_interval(&self, r: &Ray, ray_t: Interval, rec: &mut HitRecord) -> bool {
-        let oc = self.center - r.origin();
+        let current_center = self.center.at(r.time());
+        let oc = current_center - r.origin();
         let a = r.direction().length_squared();
         let h = dot(r.direction(), oc);
         let c = oc.length_squared() - self.radius * self.radius;
@@ -68,10 +123,135 @@ impl Hittable for Sphere {
 
         rec.t = root;
         rec.p = r.at(rec.t);
-        let outward_normal = (rec.p - self.center) / self.radius;
+        let outward_normal = (rec.p - current_center) / self.radius;
         rec.set_face_normal(r, &outward_normal);
+        (rec.u, rec.v) = Self::get_sphere_uv(&outward_normal);
+        rec.tangent = Self::tangent(&outward_normal);
         rec.mat = self.mat.clone();
 
-        return true;
+        true
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.bbox
+    }
+
+    // Uniformly sample the cone of directions from origin towards the sphere
+    fn sample(&self, origin: &Point3, time: f64, sampler: &mut dyn Sampler) -> Option<ShapeSample> {
+        let cos_theta_max = self.cone_cos_theta_max(origin, time)?;
+        let (r1, r2) = sampler.get_2d();
+        let z = 1.0 + r2 * (cos_theta_max - 1.0);
+        let phi = 2.0 * PI * r1;
+        let sin_theta = (1.0 - z * z).max(0.0).sqrt();
+        let local = Vec3::new(phi.cos() * sin_theta, phi.sin() * sin_theta, z);
+        let direction = Onb::new(self.center.at(time) - *origin).transform(local);
+
+        // Nearest intersection along the sampled direction, which has unit length
+        let oc = self.center.at(time) - *origin;
+        let h = dot(direction, oc);
+        let discriminant = (h * h - oc.length_squared() + self.radius * self.radius).max(0.0);
+        let p = *origin + (h - discriminant.sqrt()) * direction;
+
+        Some(ShapeSample {
+            p,
+            pdf: 1.0 / (2.0 * PI * (1.0 - cos_theta_max)),
+        })
+    }
+
+    fn pdf_value(&self, r: &Ray) -> f64 {
+        let Some(cos_theta_max) = self.cone_cos_theta_max(&r.origin(), r.time()) else {
+            return 0.0;
+        };
+        let mut rec = HitRecord::new();
+        if !self.hit_interval(r, Interval::new(0.001, INFINITY), &mut rec) {
+            return 0.0;
+        }
+        1.0 / (2.0 * PI * (1.0 - cos_theta_max))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{DefaultMaterial, IndependentSampler};
+
+    #[test]
+    fn test_sample_matches_pdf_value() {
+        let sphere = Sphere::new(
+            Point3::new(1.0, 2.0, -4.0),
+            0.5,
+            Arc::new(DefaultMaterial::new()),
+        );
+        let origin = Point3::new(0.0, 0.0, 0.0);
+        let mut sampler = IndependentSampler::new(5);
+
+        for _ in 0..100 {
+            let sample = sphere.sample(&origin, 0.0, &mut sampler).unwrap();
+            let distance = (sample.p - Point3::new(1.0, 2.0, -4.0)).length();
+            assert!((distance - 0.5).abs() < 1e-9);
+            let r = Ray::new(origin, sample.p - origin);
+            assert!((sphere.pdf_value(&r) - sample.pdf).abs() < 1e-9 * sample.pdf);
+        }
+
+        // Nothing to sample from inside the sphere
+        let inside = Point3::new(1.0, 2.0, -4.2);
+        assert!(sphere.sample(&inside, 0.0, &mut sampler).is_none());
+        let away = Ray::new(origin, Vec3::new(0.0, 0.0, 1.0));
+        assert_eq!(sphere.pdf_value(&away), 0.0);
+    }
+
+    #[test]
+    fn test_moving_sphere_follows_its_path() {
+        let sphere = Sphere::new_moving(
+            Point3::new(0.0, 0.0, -3.0),
+            Point3::new(2.0, 0.0, -3.0),
+            0.5,
+            Arc::new(DefaultMaterial::new()),
+        );
+        let origin = Point3::new(0.0, 0.0, 0.0);
+        let towards_end = Vec3::new(2.0, 0.0, -3.0);
+        let mut rec = HitRecord::new();
+
+        // The ray aimed at center2 only hits once the sphere has moved there
+        let at_end = Ray::new_with_time(origin, towards_end, 1.0);
+        assert!(sphere.hit_interval(&at_end, Interval::new(0.001, INFINITY), &mut rec));
+        assert!(((rec.p - Point3::new(2.0, 0.0, -3.0)).length() - 0.5).abs() < 1e-9);
+        let at_start = Ray::new_with_time(origin, towards_end, 0.0);
+        assert!(!sphere.hit_interval(&at_start, Interval::new(0.001, INFINITY), &mut rec));
+        let halfway = Ray::new_with_time(origin, Vec3::new(1.0, 0.0, -3.0), 0.5);
+        assert!(sphere.hit_interval(&halfway, Interval::new(0.001, INFINITY), &mut rec));
+
+        // The box covers the sphere at both ends of the motion
+        let bbox = sphere.bounding_box();
+        assert_eq!(bbox.x, Interval::new(-0.5, 2.5));
+        assert_eq!(bbox.y, Interval::new(-0.5, 0.5));
+        assert_eq!(bbox.z, Interval::new(-3.5, -2.5));
+    }
+
+    #[test]
+    fn test_shading_frame_is_continuous() {
+        let sphere = Sphere::new(
+            Point3::new(0.0, 0.0, 0.0),
+            1.0,
+            Arc::new(DefaultMaterial::new()),
+        );
+
+        // Sweep hits across normals with |n.x| = 0.9, where Onb::new switches its helper axis.
+        // The tangent frame turns smoothly and follows increasing u.
+        let mut previous: Option<(Vec3, f64)> = None;
+        for k in 0..200 {
+            let angle = 0.3 + 0.5 * k as f64 / 200.0;
+            let target = Point3::new(angle.cos(), 0.3, -angle.sin());
+            let r = Ray::new(3.0 * target, -target);
+            let mut rec = HitRecord::new();
+            assert!(sphere.hit_interval(&r, Interval::new(0.001, INFINITY), &mut rec));
+            let u_axis = rec.shading_frame().u();
+            assert!(dot(u_axis, rec.normal).abs() < 1e-9);
+            if let Some((previous_u, previous_coordinate)) = previous {
+                assert!((u_axis - previous_u).length() < 0.05);
+                assert!(rec.u > previous_coordinate);
+            }
+            previous = Some((u_axis, rec.u));
+        }
     }
 }
@@ -0,0 +1,198 @@
+use crate::{srgb_to_linear, Color, Interval, Perlin, Point3};
+use std::path::Path;
+use std::sync::Arc;
+
+// A color that varies over a surface, looked up by texture coordinates and hit point
+pub trait Texture: Send + Sync {
+    fn value(&self, u: f64, v: f64, p: &Point3) -> Color;
+}
+
+pub struct SolidColor {
+    albedo: Color,
+}
+
+impl SolidColor {
+    pub fn new(albedo: Color) -> Self {
+        SolidColor { albedo }
+    }
+}
+
+impl Texture for SolidColor {
+    fn value(&self, _u: f64, _v: f64, _p: &Point3) -> Color {
+        self.albedo
+    }
+}
+
+// Alternates between two textures on a 3D lattice of cubes
+pub struct CheckerTexture {
+    inv_scale: f64,
+    even: Arc<dyn Texture>,
+    odd: Arc<dyn Texture>,
+}
+
+impl CheckerTexture {
+    pub fn new(scale: f64, even: Arc<dyn Texture>, odd: Arc<dyn Texture>) -> Self {
+        CheckerTexture {
+            inv_scale: 1.0 / scale,
+            even,
+            odd,
+        }
+    }
+
+    pub fn from_colors(scale: f64, c1: Color, c2: Color) -> Self {
+        Self::new(
+            scale,
+            Arc::new(SolidColor::new(c1)),
+            Arc::new(SolidColor::new(c2)),
+        )
+    }
+}
+
+impl Texture for CheckerTexture {
+    fn value(&self, u: f64, v: f64, p: &Point3) -> Color {
+        let x_integer = (self.inv_scale * p.x()).floor() as i64;
+        let y_integer = (self.inv_scale * p.y()).floor() as i64;
+        let z_integer = (self.inv_scale * p.z()).floor() as i64;
+
+        let is_even = (x_integer + y_integer + z_integer) % 2 == 0;
+        if is_even {
+            self.even.value(u, v, p)
+        } else {
+            self.odd.value(u, v, p)
+        }
+    }
+}
+
+// Texture backed by an image file. Pixels are stored as linear colors.
+pub struct ImageTexture {
+    width: u32,
+    height: u32,
+    pixels: Vec<Color>,
+}
+
+impl ImageTexture {
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, image::ImageError> {
+        let img = image::open(path)?.into_rgb8();
+        let (width, height) = img.dimensions();
+        let pixels = img
+            .pixels()
+            .map(|p| {
+                Color::new(
+                    srgb_to_linear(p[0] as f64 / 255.0),
+                    srgb_to_linear(p[1] as f64 / 255.0),
+                    srgb_to_linear(p[2] as f64 / 255.0),
+                )
+            })
+            .collect();
+        Ok(ImageTexture {
+            width,
+            height,
+            pixels,
+        })
+    }
+}
+
+impl Texture for ImageTexture {
+    fn value(&self, u: f64, v: f64, _p: &Point3) -> Color {
+        // If we have no texture data, then return solid cyan as a debugging aid
+        if self.height == 0 || self.width == 0 {
+            return Color::new(0.0, 1.0, 1.0);
+        }
+
+        // Clamp input texture coordinates to [0,1] x [1,0]
+        let u = Interval::new(0.0, 1.0).clamp(u);
+        let v = 1.0 - Interval::new(0.0, 1.0).clamp(v); // Flip V to image coordinates
+
+        let i = ((u * self.width as f64) as u32).min(self.width - 1);
+        let j = ((v * self.height as f64) as u32).min(self.height - 1);
+        self.pixels[(j * self.width + i) as usize]
+    }
+}
+
+// Marble-like turbulence built from Perlin noise
+pub struct NoiseTexture {
+    noise: Perlin,
+    scale: f64,
+}
+
+impl NoiseTexture {
+    pub fn new(scale: f64) -> Self {
+        NoiseTexture {
+            noise: Perlin::new(),
+            scale,
+        }
+    }
+}
+
+impl Texture for NoiseTexture {
+    fn value(&self, _u: f64, _v: f64, p: &Point3) -> Color {
+        Color::new(0.5, 0.5, 0.5)
+            * (1.0 + f64::sin(self.scale * p.z() + 10.0 * self.noise.turb(p, 7)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_checker_alternates_by_cell_parity() {
+        let black = Color::new(0.0, 0.0, 0.0);
+        let white = Color::new(1.0, 1.0, 1.0);
+        let checker = CheckerTexture::from_colors(0.5, black, white);
+        let at = |x: f64, y: f64, z: f64| checker.value(0.0, 0.0, &Point3::new(x, y, z));
+
+        assert_eq!(at(0.1, 0.1, 0.1), black);
+        assert_eq!(at(0.6, 0.1, 0.1), white);
+        assert_eq!(at(0.6, 0.6, 0.1), black);
+        assert_eq!(at(0.6, 0.6, 0.6), white);
+        // Cells below zero keep alternating instead of mirroring about the origin
+        assert_eq!(at(-0.1, 0.1, 0.1), white);
+        assert_eq!(at(-0.6, 0.1, 0.1), black);
+    }
+
+    #[test]
+    fn test_image_texture_looks_up_decoded_pixels() {
+        // Top row red and green, bottom row blue and mid gray
+        let mut img = image::RgbImage::new(2, 2);
+        img.put_pixel(0, 0, image::Rgb([255, 0, 0]));
+        img.put_pixel(1, 0, image::Rgb([0, 255, 0]));
+        img.put_pixel(0, 1, image::Rgb([0, 0, 255]));
+        img.put_pixel(1, 1, image::Rgb([128, 128, 128]));
+        let dir = std::env::temp_dir().join("pathtracer_texture_test");
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("quad.png");
+        img.save(&path).unwrap();
+
+        let tex = ImageTexture::load(&path).unwrap();
+        let p = Point3::new(0.0, 0.0, 0.0);
+        // v = 1 is the top of the image
+        assert_eq!(tex.value(0.25, 0.75, &p), Color::new(1.0, 0.0, 0.0));
+        assert_eq!(tex.value(0.75, 0.75, &p), Color::new(0.0, 1.0, 0.0));
+        assert_eq!(tex.value(0.25, 0.25, &p), Color::new(0.0, 0.0, 1.0));
+        // Coordinates outside [0, 1] clamp to the edge
+        assert_eq!(tex.value(-3.0, 2.0, &p), Color::new(1.0, 0.0, 0.0));
+
+        // 8-bit values are sRGB encoded and stored linear
+        let gray = tex.value(1.0, 0.0, &p);
+        let expected = srgb_to_linear(128.0 / 255.0);
+        assert!((gray.x() - expected).abs() < 1e-12);
+        assert!((gray.x() - 0.2158).abs() < 1e-3);
+
+        assert!(ImageTexture::load(dir.join("missing.png")).is_err());
+    }
+
+    #[test]
+    fn test_noise_texture_is_repeatable_and_in_range() {
+        let a = NoiseTexture::new(4.0);
+        let b = NoiseTexture::new(4.0);
+        for k in 0..500 {
+            let t = k as f64 * 0.137;
+            let p = Point3::new(t.sin() * 3.0, t * 0.5, t.cos() * 2.0 - 1.0);
+            let value = a.value(0.0, 0.0, &p);
+            assert_eq!(value, b.value(0.0, 0.0, &p));
+            assert_eq!(value.x(), value.y());
+            assert!((0.0..=1.0).contains(&value.x()));
+        }
+    }
+}
@@ -0,0 +1,108 @@
+use crate::Color;
+
+// Curve compressing scene-referred radiance into the displayable [0,1] range
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ToneMapOperator {
+    Clamp,                                 // Clip everything above 1
+    Reinhard,                              // c / (1 + c)
+    ReinhardExtended { white_point: f64 }, // Reinhard that maps white_point to 1
+    Aces,                                  // Narkowicz's fit of the ACES filmic curve
+    Hable,                                 // John Hable's Uncharted 2 filmic curve
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ToneMapper {
+    pub exposure: f64, // Exposure compensation in stops, applied before the curve
+    pub operator: ToneMapOperator,
+}
+
+impl Default for ToneMapper {
+    fn default() -> Self {
+        Self {
+            exposure: 0.0,
+            operator: ToneMapOperator::Clamp,
+        }
+    }
+}
+
+impl ToneMapper {
+    pub fn new(exposure: f64, operator: ToneMapOperator) -> Self {
+        Self { exposure, operator }
+    }
+
+    // Map a linear color to linear display values in [0,1]
+    pub fn map(&self, color: Color) -> Color {
+        let scale = f64::powf(2.0, self.exposure);
+        let c = color * scale;
+        Color::new(
+            self.map_channel(c.x()),
+            self.map_channel(c.y()),
+            self.map_channel(c.z()),
+        )
+    }
+
+    fn map_channel(&self, x: f64) -> f64 {
+        let x = f64::max(x, 0.0);
+        let mapped = match self.operator {
+            ToneMapOperator::Clamp => x,
+            ToneMapOperator::Reinhard => x / (1.0 + x),
+            ToneMapOperator::ReinhardExtended { white_point } => {
+                x * (1.0 + x / (white_point * white_point)) / (1.0 + x)
+            }
+            ToneMapOperator::Aces => {
+                let (a, b, c, d, e) = (2.51, 0.03, 2.43, 0.59, 0.14);
+                (x * (a * x + b)) / (x * (c * x + d) + e)
+            }
+            ToneMapOperator::Hable => {
+                const EXPOSURE_BIAS: f64 = 2.0;
+                const WHITE_POINT: f64 = 11.2;
+                hable_partial(x * EXPOSURE_BIAS) / hable_partial(WHITE_POINT)
+            }
+        };
+        mapped.clamp(0.0, 1.0)
+    }
+}
+
+fn hable_partial(x: f64) -> f64 {
+    let (a, b, c, d, e, f) = (0.15, 0.50, 0.10, 0.20, 0.02, 0.30);
+    ((x * (a * x + c * b) + d * e) / (x * (a * x + b) + d * f)) - e / f
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const OPERATORS: [ToneMapOperator; 5] = [
+        ToneMapOperator::Clamp,
+        ToneMapOperator::Reinhard,
+        ToneMapOperator::ReinhardExtended { white_point: 4.0 },
+        ToneMapOperator::Aces,
+        ToneMapOperator::Hable,
+    ];
+
+    #[test]
+    fn test_operators_stay_in_range_and_are_monotonic() {
+        for operator in OPERATORS {
+            let mapper = ToneMapper::new(0.0, operator);
+            let mut previous = 0.0;
+            for i in 0..200 {
+                let value = mapper.map(Color::new(i as f64 * 0.1, 0.0, 0.0)).x();
+                assert!((0.0..=1.0).contains(&value), "{:?}", operator);
+                assert!(value >= previous, "{:?}", operator);
+                previous = value;
+            }
+        }
+    }
+
+    #[test]
+    fn test_exposure_and_white_point() {
+        let mapper = ToneMapper::new(1.0, ToneMapOperator::Clamp);
+        assert_eq!(
+            mapper.map(Color::new(0.25, 0.5, 2.0)),
+            Color::new(0.5, 1.0, 1.0)
+        );
+
+        let mapper = ToneMapper::new(0.0, ToneMapOperator::ReinhardExtended { white_point: 4.0 });
+        assert!((mapper.map(Color::new(4.0, 4.0, 4.0)).x() - 1.0).abs() < 1e-12);
+    }
+}
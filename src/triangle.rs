@@ -0,0 +1,185 @@
+use crate::{
+    dot, unit_vector, vec3::cross, Aabb, HitRecord, Hittable, Interval, Material, Point3, Ray, Vec3,
+};
+use std::sync::Arc;
+
+pub struct Triangle {
+    v0: Point3,
+    v1: Point3,
+    v2: Point3,
+    mat: Arc<dyn Material>,
+    bbox: Aabb,
+}
+
+impl Triangle {
+    pub fn new(v0: Point3, v1: Point3, v2: Point3, mat: Arc<dyn Material>) -> Self {
+        Self {
+            v0,
+            v1,
+            v2,
+            mat,
+            bbox: triangle_bounding_box(v0, v1, v2),
+        }
+    }
+}
+
+impl Hittable for Triangle {
+    fn hit_tmin_tmax(&self, r: &Ray, ray_tmin: f64, ray_tmax: f64, rec: &mut HitRecord) -> bool {
+        self.hit_interval(r, Interval::new(ray_tmin, ray_tmax), rec)
+    }
+
+    fn hit_interval(&self, r: &Ray, ray_t: Interval, rec: &mut HitRecord) -> bool {
+        let Some((t, b1, b2)) = intersect_triangle(r, ray_t, self.v0, self.v1, self.v2) else {
+            return false;
+        };
+
+        rec.t = t;
+        rec.p = r.at(t);
+        let outward_normal = unit_vector(cross(self.v1 - self.v0, self.v2 - self.v0));
+        rec.set_face_normal(r, &outward_normal);
+        rec.barycentric = Vec3::new(1.0 - b1 - b2, b1, b2);
+        rec.u = b1;
+        rec.v = b2;
+        rec.tangent = self.v1 - self.v0;
+        rec.mat = self.mat.clone();
+
+        true
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.bbox
+    }
+}
+
+pub(crate) fn triangle_bounding_box(v0: Point3, v1: Point3, v2: Point3) -> Aabb {
+    Aabb::surrounding(&Aabb::from_points(v0, v1), &Aabb::from_points(v0, v2))
+}
+
+// Möller–Trumbore ray/triangle intersection. On a hit, returns the ray parameter t and the
+// barycentric weights (b1, b2) of v1 and v2; the weight of v0 is 1 - b1 - b2.
+pub(crate) fn intersect_triangle(
+    r: &Ray,
+    ray_t: Interval,
+    v0: Point3,
+    v1: Point3,
+    v2: Point3,
+) -> Option<(f64, f64, f64)> {
+    let edge1 = v1 - v0;
+    let edge2 = v2 - v0;
+    let pvec = cross(r.direction(), edge2);
+    let det = dot(edge1, pvec);
+
+    // The ray is parallel to the triangle plane
+    if det.abs() < 1e-12 {
+        return None;
+    }
+    let inv_det = 1.0 / det;
+
+    let tvec = r.origin() - v0;
+    let b1 = dot(tvec, pvec) * inv_det;
+    if !(0.0..=1.0).contains(&b1) {
+        return None;
+    }
+
+    let qvec = cross(tvec, edge1);
+    let b2 = dot(r.direction(), qvec) * inv_det;
+    if b2 < 0.0 || b1 + b2 > 1.0 {
+        return None;
+    }
+
+    let t = dot(edge2, qvec) * inv_det;
+    if !ray_t.surrounds(t) {
+        return None;
+    }
+
+    Some((t, b1, b2))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{DefaultMaterial, INFINITY};
+
+    fn test_triangle() -> Triangle {
+        Triangle::new(
+            Point3::new(0.0, 0.0, -2.0),
+            Point3::new(2.0, 0.0, -2.0),
+            Point3::new(0.0, 2.0, -2.0),
+            Arc::new(DefaultMaterial::new()),
+        )
+    }
+
+    fn shoot(triangle: &Triangle, x: f64, y: f64, rec: &mut HitRecord) -> bool {
+        let r = Ray::new(Point3::new(x, y, 0.0), Vec3::new(0.0, 0.0, -1.0));
+        triangle.hit_interval(&r, Interval::new(0.001, INFINITY), rec)
+    }
+
+    #[test]
+    fn test_hit_and_miss() {
+        let triangle = test_triangle();
+        let mut rec = HitRecord::new();
+
+        assert!(shoot(&triangle, 0.5, 0.5, &mut rec));
+        assert!((rec.t - 2.0).abs() < 1e-12);
+        assert!((rec.p - Point3::new(0.5, 0.5, -2.0)).length() < 1e-12);
+        assert_eq!(rec.normal, Vec3::new(0.0, 0.0, 1.0));
+        assert!(rec.front_face);
+
+        // Beyond the hypotenuse and behind the ray origin
+        assert!(!shoot(&triangle, 1.5, 1.5, &mut rec));
+        let behind = Ray::new(Point3::new(0.5, 0.5, -3.0), Vec3::new(0.0, 0.0, -1.0));
+        assert!(!triangle.hit_interval(&behind, Interval::new(0.001, INFINITY), &mut rec));
+    }
+
+    #[test]
+    fn test_back_face_flips_normal() {
+        let triangle = test_triangle();
+        let r = Ray::new(Point3::new(0.5, 0.5, -4.0), Vec3::new(0.0, 0.0, 1.0));
+        let mut rec = HitRecord::new();
+
+        assert!(triangle.hit_interval(&r, Interval::new(0.001, INFINITY), &mut rec));
+        assert!(!rec.front_face);
+        assert_eq!(rec.normal, Vec3::new(0.0, 0.0, -1.0));
+    }
+
+    #[test]
+    fn test_edges() {
+        let triangle = test_triangle();
+        let mut rec = HitRecord::new();
+        let eps = 1e-9;
+
+        for (inside, outside) in [
+            ((1.0, eps), (1.0, -eps)),                        // edge v0-v1
+            ((eps, 1.0), (-eps, 1.0)),                        // edge v0-v2
+            ((1.0 - eps, 1.0 - eps), (1.0 + eps, 1.0 + eps)), // edge v1-v2
+        ] {
+            assert!(shoot(&triangle, inside.0, inside.1, &mut rec));
+            assert!(!shoot(&triangle, outside.0, outside.1, &mut rec));
+        }
+    }
+
+    #[test]
+    fn test_parallel_ray_misses() {
+        let triangle = test_triangle();
+        let r = Ray::new(Point3::new(-1.0, 0.5, -2.0), Vec3::new(1.0, 0.0, 0.0));
+        let mut rec = HitRecord::new();
+
+        assert!(!triangle.hit_interval(&r, Interval::new(0.001, INFINITY), &mut rec));
+    }
+
+    #[test]
+    fn test_barycentric_and_uv() {
+        let triangle = test_triangle();
+        let mut rec = HitRecord::new();
+
+        assert!(shoot(&triangle, 0.5, 1.0, &mut rec));
+        let b = rec.barycentric;
+        assert!((b.x() + b.y() + b.z() - 1.0).abs() < 1e-12);
+        assert!((b - Vec3::new(0.25, 0.25, 0.5)).length() < 1e-12);
+        assert_eq!((rec.u, rec.v), (b.y(), b.z()));
+
+        // The weights reconstruct the hit point from the vertices
+        let p = b.x() * triangle.v0 + b.y() * triangle.v1 + b.z() * triangle.v2;
+        assert!((p - rec.p).length() < 1e-12);
+    }
+}
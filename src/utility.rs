@@ -1,7 +1,50 @@
-pub fn random_double() -> f64 {
-    rand::random::<f64>()
+use crate::Ray;
+
+// Small, fast generator (SplitMix64). Every random draw in a render comes from one of these,
+// seeded explicitly, so a render never depends on thread scheduling.
+#[derive(Debug, Clone)]
+pub struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        Self { state: seed }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        mix64(self.state)
+    }
+
+    // Uniform in [0, 1)
+    pub fn random_double(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 * (1.0 / (1u64 << 53) as f64)
+    }
+
+    pub fn random_double_range(&mut self, min: f64, max: f64) -> f64 {
+        min + (max - min) * self.random_double()
+    }
+}
+
+// Combine several values into one well-mixed seed
+pub fn hash_seed(values: &[u64]) -> u64 {
+    values
+        .iter()
+        .fold(0x853C_49E6_748F_EA9B, |h, &v| mix64(h ^ mix64(v)))
+}
+
+// Generator seeded by the ray itself, for code that must make random choices without a
+// sampler, like hit tests inside participating media. The same ray always gets the same draws.
+pub fn ray_rng(r: &Ray) -> Rng {
+    let (o, d) = (r.origin(), r.direction());
+    let bits = [o.x(), o.y(), o.z(), d.x(), d.y(), d.z(), r.time()].map(f64::to_bits);
+    Rng::new(hash_seed(&bits))
 }
 
-pub fn random_double_range(min: f64, max: f64) -> f64 {
-    min + (max - min) * random_double()
+// Finalizer of SplitMix64
+fn mix64(mut z: u64) -> u64 {
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
 }
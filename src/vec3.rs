@@ -1,6 +1,6 @@
 use std::ops::{Add, AddAssign, Div, DivAssign, Index, IndexMut, Mul, MulAssign, Neg, Sub};
 
-use crate::{random_double, random_double_range};
+use crate::{Sampler, PI};
 
 #[derive(Debug, PartialEq, Clone, Copy)]
 pub struct Vec3 {
@@ -38,16 +38,20 @@ impl Vec3 {
         self.e[2]
     }
 
-    pub fn random() -> Vec3 {
-        return Vec3::new(random_double(), random_double(), random_double());
+    pub fn random(sampler: &mut dyn Sampler) -> Vec3 {
+        Vec3::new(
+            sampler.random_double(),
+            sampler.random_double(),
+            sampler.random_double(),
+        )
     }
 
-    pub fn random_range(min: f64, max: f64) -> Vec3 {
-        return Vec3::new(
-            random_double_range(min, max),
-            random_double_range(min, max),
-            random_double_range(min, max),
-        );
+    pub fn random_range(min: f64, max: f64, sampler: &mut dyn Sampler) -> Vec3 {
+        Vec3::new(
+            sampler.random_double_range(min, max),
+            sampler.random_double_range(min, max),
+            sampler.random_double_range(min, max),
+        )
     }
 }
 
@@ -187,22 +191,45 @@ pub fn unit_vector(v: Vec3) -> Vec3 {
     v / v.length()
 }
 
-pub fn random_unit_vector() -> Vec3 {
-    loop {
-        let p = Vec3::random_range(-1.0, 1.0);
-        let lensq = p.length_squared();
-        if 1e-160 < lensq && lensq <= 1.0 {
-            return p / lensq.sqrt();
-        }
+// Uniform direction on the unit sphere, from one 2D sample
+pub fn random_unit_vector(sampler: &mut dyn Sampler) -> Vec3 {
+    let (u, v) = sampler.get_2d();
+    let z = 1.0 - 2.0 * u;
+    let r = (1.0 - z * z).max(0.0).sqrt();
+    let phi = 2.0 * PI * v;
+    Vec3::new(r * phi.cos(), r * phi.sin(), z)
+}
+
+// Uniform point in the unit disk, from one 2D sample. The concentric mapping keeps
+// neighbouring samples close, which preserves the stratification of the sampler.
+pub fn random_in_unit_disk(sampler: &mut dyn Sampler) -> Vec3 {
+    let (u, v) = sampler.get_2d();
+    let (x, y) = (2.0 * u - 1.0, 2.0 * v - 1.0);
+    if x == 0.0 && y == 0.0 {
+        return Vec3::new(0.0, 0.0, 0.0);
     }
+    let (r, theta) = if x.abs() > y.abs() {
+        (x, PI / 4.0 * (y / x))
+    } else {
+        (y, PI / 2.0 - PI / 4.0 * (x / y))
+    };
+    Vec3::new(r * theta.cos(), r * theta.sin(), 0.0)
+}
+
+// Cosine-weighted direction around +z, from one 2D sample
+pub fn random_cosine_direction(sampler: &mut dyn Sampler) -> Vec3 {
+    let (r1, r2) = sampler.get_2d();
+    let phi = 2.0 * PI * r1;
+    let r = r2.sqrt();
+    Vec3::new(r * phi.cos(), r * phi.sin(), (1.0 - r2).sqrt())
 }
 
-pub fn random_on_hemisphere(normal: &Vec3) -> Vec3 {
-    let on_unit_sphere = random_unit_vector();
+pub fn random_on_hemisphere(normal: &Vec3, sampler: &mut dyn Sampler) -> Vec3 {
+    let on_unit_sphere = random_unit_vector(sampler);
     if dot(on_unit_sphere, *normal) > 0.0 {
-        return on_unit_sphere;
+        on_unit_sphere
     } else {
-        return -on_unit_sphere;
+        -on_unit_sphere
     }
 }
 
@@ -217,6 +244,69 @@ pub fn refract(uv: Vec3, n: Vec3, etai_over_etat: f64) -> Vec3 {
     r_out_perp + r_out_parallel
 }
 
+// Orthonormal basis whose w axis points along a given direction
+#[derive(Debug, Clone, Copy)]
+pub struct Onb {
+    axis: [Vec3; 3],
+}
+
+impl Onb {
+    pub fn new(n: Vec3) -> Self {
+        let w = unit_vector(n);
+        let a = if w.x().abs() > 0.9 {
+            Vec3::new(0.0, 1.0, 0.0)
+        } else {
+            Vec3::new(1.0, 0.0, 0.0)
+        };
+        let v = unit_vector(cross(w, a));
+        let u = cross(v, w);
+        Self { axis: [u, v, w] }
+    }
+
+    // Basis with w along n and u along the part of tangent perpendicular to n, falling back to
+    // new(n) when the tangent is missing or parallel to n
+    pub fn from_normal_tangent(n: Vec3, tangent: Vec3) -> Self {
+        if tangent.near_zero() {
+            return Self::new(n);
+        }
+        let w = unit_vector(n);
+        let t = unit_vector(tangent);
+        let u = t - dot(t, w) * w;
+        if u.length_squared() < 1e-12 {
+            return Self::new(n);
+        }
+        let u = unit_vector(u);
+        let v = cross(w, u);
+        Self { axis: [u, v, w] }
+    }
+
+    pub fn u(&self) -> Vec3 {
+        self.axis[0]
+    }
+
+    pub fn v(&self) -> Vec3 {
+        self.axis[1]
+    }
+
+    pub fn w(&self) -> Vec3 {
+        self.axis[2]
+    }
+
+    // Convert a vector expressed in this basis to world coordinates
+    pub fn transform(&self, v: Vec3) -> Vec3 {
+        v[0] * self.axis[0] + v[1] * self.axis[1] + v[2] * self.axis[2]
+    }
+
+    // Express a world vector in this basis
+    pub fn to_local(&self, v: Vec3) -> Vec3 {
+        Vec3::new(
+            dot(v, self.axis[0]),
+            dot(v, self.axis[1]),
+            dot(v, self.axis[2]),
+        )
+    }
+}
+
 // Unit tests for Vec3
 #[cfg(test)]
 mod tests {
@@ -322,4 +412,36 @@ mod tests {
         let x: f64 = 5.0;
         assert_eq!(v1 * x, Vec3::new(15.0, 20.0, 0.0));
     }
+
+    #[test]
+    fn test_onb_is_orthonormal() {
+        for n in [
+            Vec3::new(0.0, 0.0, 2.0),
+            Vec3::new(1.0, 0.0, 0.0),
+            Vec3::new(-0.3, 0.5, 0.8),
+        ] {
+            let onb = Onb::new(n);
+            assert!((onb.w() - unit_vector(n)).near_zero());
+            assert!(dot(onb.u(), onb.v()).abs() < 1e-12);
+            assert!(dot(onb.u(), onb.w()).abs() < 1e-12);
+            assert!((onb.u().length() - 1.0).abs() < 1e-12);
+            assert!((cross(onb.u(), onb.v()) - onb.w()).near_zero());
+            assert!((onb.transform(Vec3::new(0.0, 0.0, 1.0)) - onb.w()).near_zero());
+        }
+    }
+
+    #[test]
+    fn test_onb_follows_tangent() {
+        let n = Vec3::new(0.0, 1.0, 0.0);
+        let onb = Onb::from_normal_tangent(n, Vec3::new(2.0, 0.5, 0.0));
+        assert!((onb.u() - Vec3::new(1.0, 0.0, 0.0)).near_zero());
+        assert!((onb.v() - Vec3::new(0.0, 0.0, -1.0)).near_zero());
+        assert!((cross(onb.u(), onb.v()) - onb.w()).near_zero());
+
+        // Without a usable tangent the frame comes from the normal alone
+        for tangent in [Vec3::new(0.0, 0.0, 0.0), Vec3::new(0.0, 3.0, 0.0)] {
+            let onb = Onb::from_normal_tangent(n, tangent);
+            assert!((onb.u() - Onb::new(n).u()).near_zero());
+        }
+    }
 }
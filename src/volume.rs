@@ -0,0 +1,301 @@
+use crate::{Aabb, Interval, Point3};
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+#[derive(Debug)]
+pub enum VolumeError {
+    Io {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    Format {
+        path: PathBuf,
+        message: String,
+    },
+}
+
+impl fmt::Display for VolumeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            VolumeError::Io { path, source } => write!(f, "{}: {}", path.display(), source),
+            VolumeError::Format { path, message } => write!(f, "{}: {}", path.display(), message),
+        }
+    }
+}
+
+impl std::error::Error for VolumeError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            VolumeError::Io { source, .. } => Some(source),
+            VolumeError::Format { .. } => None,
+        }
+    }
+}
+
+// Dense voxel grid of densities stretched over a box, with x varying fastest in memory.
+// Voxel values sit at voxel centers and are interpolated trilinearly in between.
+pub struct DensityGrid {
+    resolution: [usize; 3],
+    data: Vec<f32>,
+    bounds: Aabb,
+    max_value: f64,
+}
+
+impl DensityGrid {
+    // Returns None if data does not hold one value per voxel, or holds a negative or
+    // non-finite one. The grid fills the unit cube until placed elsewhere with with_bounds.
+    pub fn new(resolution: [usize; 3], data: Vec<f32>) -> Option<Self> {
+        let count = resolution.iter().product::<usize>();
+        if count == 0 || data.len() != count || !data.iter().all(valid_density) {
+            return None;
+        }
+        let max_value = data.iter().fold(0.0f64, |max, &v| max.max(v as f64));
+        Some(Self {
+            resolution,
+            data,
+            bounds: Aabb::from_points(Point3::new(0.0, 0.0, 0.0), Point3::new(1.0, 1.0, 1.0)),
+            max_value,
+        })
+    }
+
+    pub fn with_bounds(mut self, min: Point3, max: Point3) -> Self {
+        self.bounds = Aabb::from_points(min, max);
+        self
+    }
+
+    pub fn resolution(&self) -> [usize; 3] {
+        self.resolution
+    }
+
+    pub fn bounds(&self) -> Aabb {
+        self.bounds
+    }
+
+    // Largest voxel value, which bounds every interpolated density
+    pub fn max_value(&self) -> f64 {
+        self.max_value
+    }
+
+    // Interpolated density at p; zero outside the bounds
+    pub fn density(&self, p: Point3) -> f64 {
+        let mut base = [0usize; 3];
+        let mut frac = [0.0; 3];
+        for axis in 0..3 {
+            let extent: &Interval = self.bounds.axis_interval(axis);
+            let offset = (p[axis] - extent.min) / extent.size();
+            if !(0.0..=1.0).contains(&offset) {
+                return 0.0;
+            }
+
+            // Continuous voxel coordinate, clamped so the outer half voxels hold their value
+            let n = self.resolution[axis];
+            let x = (offset * n as f64 - 0.5).clamp(0.0, (n - 1) as f64);
+            base[axis] = usize::min(x as usize, n.saturating_sub(2));
+            frac[axis] = x - base[axis] as f64;
+        }
+
+        let mut density = 0.0;
+        for corner in 0..8 {
+            let mut weight = 1.0;
+            let mut index = [0usize; 3];
+            for axis in 0..3 {
+                let step = (corner >> axis) & 1;
+                index[axis] = usize::min(base[axis] + step, self.resolution[axis] - 1);
+                weight *= if step == 1 {
+                    frac[axis]
+                } else {
+                    1.0 - frac[axis]
+                };
+            }
+            if weight > 0.0 {
+                density += weight * self.voxel(index) as f64;
+            }
+        }
+        density
+    }
+
+    fn voxel(&self, [x, y, z]: [usize; 3]) -> f32 {
+        self.data[(z * self.resolution[1] + y) * self.resolution[0] + x]
+    }
+}
+
+// Load headerless little-endian 32-bit floats, x varying fastest
+pub fn load_raw(
+    path: impl AsRef<Path>,
+    resolution: [usize; 3],
+) -> Result<DensityGrid, VolumeError> {
+    let path = path.as_ref();
+    let bytes = read(path)?;
+    let count = resolution.iter().product::<usize>();
+    if bytes.len() != 4 * count {
+        return Err(format_error(
+            path,
+            format!(
+                "expected {} bytes for a {}x{}x{} grid, found {}",
+                4 * count,
+                resolution[0],
+                resolution[1],
+                resolution[2],
+                bytes.len()
+            ),
+        ));
+    }
+    let data = bytes
+        .chunks_exact(4)
+        .map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+        .collect();
+    build_grid(path, resolution, data)
+}
+
+// Load a Mitsuba grid volume: "VOL" and version 3, then little-endian i32 encoding
+// (1 = f32, 3 = u8), resolution and channel count, six f32 bounds, and the voxels. Only the
+// first channel is kept.
+pub fn load_vol(path: impl AsRef<Path>) -> Result<DensityGrid, VolumeError> {
+    let path = path.as_ref();
+    let bytes = read(path)?;
+    if bytes.len() < 48 || &bytes[0..3] != b"VOL" || bytes[3] != 3 {
+        return Err(format_error(path, "not a version 3 .vol file"));
+    }
+
+    let word = |i: usize| [bytes[i], bytes[i + 1], bytes[i + 2], bytes[i + 3]];
+    let int = |i: usize| i32::from_le_bytes(word(i));
+    let float = |i: usize| f32::from_le_bytes(word(i)) as f64;
+
+    let encoding = int(4);
+    let dims = [int(8), int(12), int(16)];
+    let channels = int(20);
+    if dims.iter().any(|&d| d <= 0) || channels <= 0 {
+        return Err(format_error(path, "invalid resolution or channel count"));
+    }
+    let resolution = dims.map(|d| d as usize);
+    let channels = channels as usize;
+    let min = Point3::new(float(24), float(28), float(32));
+    let max = Point3::new(float(36), float(40), float(44));
+
+    let count = resolution.iter().product::<usize>();
+    let voxels = &bytes[48..];
+    let data: Vec<f32> = match encoding {
+        1 if voxels.len() == 4 * count * channels => (0..count)
+            .map(|i| f32::from_le_bytes(word(48 + 4 * i * channels)))
+            .collect(),
+        3 if voxels.len() == count * channels => (0..count)
+            .map(|i| voxels[i * channels] as f32 / 255.0)
+            .collect(),
+        1 | 3 => return Err(format_error(path, "voxel data has the wrong size")),
+        _ => {
+            return Err(format_error(
+                path,
+                format!("unsupported encoding {}", encoding),
+            ))
+        }
+    };
+
+    Ok(build_grid(path, resolution, data)?.with_bounds(min, max))
+}
+
+fn valid_density(v: &f32) -> bool {
+    v.is_finite() && *v >= 0.0
+}
+
+fn build_grid(
+    path: &Path,
+    resolution: [usize; 3],
+    data: Vec<f32>,
+) -> Result<DensityGrid, VolumeError> {
+    if !data.iter().all(valid_density) {
+        return Err(format_error(
+            path,
+            "densities must be finite and not negative",
+        ));
+    }
+    DensityGrid::new(resolution, data).ok_or_else(|| format_error(path, "empty grid"))
+}
+
+fn read(path: &Path) -> Result<Vec<u8>, VolumeError> {
+    fs::read(path).map_err(|source| VolumeError::Io {
+        path: path.to_path_buf(),
+        source,
+    })
+}
+
+fn format_error(path: &Path, message: impl Into<String>) -> VolumeError {
+    VolumeError::Format {
+        path: path.to_path_buf(),
+        message: message.into(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_density_interpolates_between_voxel_centers() {
+        let grid = DensityGrid::new([2, 1, 1], vec![0.0, 4.0]).unwrap();
+        let at = |x: f64| grid.density(Point3::new(x, 0.5, 0.5));
+        assert_eq!(at(0.1), 0.0);
+        assert!((at(0.5) - 2.0).abs() < 1e-12);
+        assert_eq!(at(0.9), 4.0);
+        assert_eq!(at(1.5), 0.0);
+        assert_eq!(grid.max_value(), 4.0);
+
+        let single = DensityGrid::new([1, 1, 1], vec![3.0]).unwrap();
+        assert_eq!(single.density(Point3::new(0.2, 0.7, 0.9)), 3.0);
+        assert!(DensityGrid::new([2, 2, 2], vec![1.0]).is_none());
+    }
+
+    #[test]
+    fn test_invalid_densities_are_rejected() {
+        assert!(DensityGrid::new([2, 1, 1], vec![1.0, -0.5]).is_none());
+        assert!(DensityGrid::new([2, 1, 1], vec![f32::NAN, 1.0]).is_none());
+        assert!(DensityGrid::new([1, 1, 1], vec![f32::INFINITY]).is_none());
+
+        let dir = std::env::temp_dir().join("pathtracer_volume_test");
+        fs::create_dir_all(&dir).unwrap();
+        for (name, value) in [("negative.raw", -1.0f32), ("nan.raw", f32::NAN)] {
+            let raw: Vec<u8> = [0.5f32, value]
+                .iter()
+                .flat_map(|v| v.to_le_bytes())
+                .collect();
+            fs::write(dir.join(name), &raw).unwrap();
+            let err = load_raw(dir.join(name), [2, 1, 1]).err().unwrap();
+            assert!(err
+                .to_string()
+                .ends_with("densities must be finite and not negative"));
+        }
+    }
+
+    #[test]
+    fn test_load_vol_and_raw() {
+        let dir = std::env::temp_dir().join("pathtracer_volume_test");
+        fs::create_dir_all(&dir).unwrap();
+
+        let mut vol = b"VOL\x03".to_vec();
+        for v in [3i32, 2, 1, 1, 1] {
+            vol.extend_from_slice(&v.to_le_bytes());
+        }
+        for v in [-1.0f32, 0.0, 0.0, 1.0, 2.0, 3.0] {
+            vol.extend_from_slice(&v.to_le_bytes());
+        }
+        vol.extend_from_slice(&[0, 255]);
+        fs::write(dir.join("grid.vol"), &vol).unwrap();
+
+        let grid = load_vol(dir.join("grid.vol")).unwrap();
+        assert_eq!(grid.resolution(), [2, 1, 1]);
+        assert_eq!(grid.bounds().x, Interval::new(-1.0, 1.0));
+        assert_eq!(grid.density(Point3::new(0.9, 1.0, 1.5)), 1.0);
+
+        let raw: Vec<u8> = [0.5f32, 1.5].iter().flat_map(|v| v.to_le_bytes()).collect();
+        fs::write(dir.join("grid.raw"), &raw).unwrap();
+        let grid = load_raw(dir.join("grid.raw"), [1, 2, 1]).unwrap();
+        assert_eq!(grid.max_value(), 1.5);
+
+        let err = load_raw(dir.join("grid.raw"), [2, 2, 1]).err().unwrap();
+        assert!(err
+            .to_string()
+            .ends_with("expected 16 bytes for a 2x2x1 grid, found 8"));
+        let err = load_vol(dir.join("grid.raw")).err().unwrap();
+        assert!(err.to_string().ends_with("not a version 3 .vol file"));
+    }
+}